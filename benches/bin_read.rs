@@ -0,0 +1,79 @@
+use std::{
+    fs::File,
+    hint::black_box,
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use yandex_practicum_rust::{
+    Parser, Transaction,
+    parsers::bin::{mmap::BinMmap, parser::BinParser},
+};
+
+const RECORDS: u64 = 100_000;
+
+fn prepare_file() -> PathBuf {
+    let path = std::env::temp_dir().join(format!("bin_read_bench_{}.bin", std::process::id()));
+    let transactions: Vec<Transaction> = (0..RECORDS)
+        .map(|tx_id| {
+            let mut transaction = Transaction::default();
+            transaction.set_tx_id(tx_id);
+            transaction.set_amount(tx_id * 10);
+            transaction.set_description(format!("Transaction number {tx_id}"));
+            transaction
+        })
+        .collect();
+    let mut writer = BufWriter::new(File::create(&path).unwrap());
+    BinParser::write_to(&mut writer, &transactions).unwrap();
+    path
+}
+
+fn bench_bin_read(c: &mut Criterion) {
+    let path = prepare_file();
+    let mut group = c.benchmark_group("bin_read");
+
+    group.bench_function("from_read", |b| {
+        b.iter(|| {
+            let mut reader = BufReader::new(File::open(&path).unwrap());
+            let transactions = BinParser::from_read(&mut reader).unwrap();
+            black_box(
+                transactions
+                    .iter()
+                    .map(|t| t.description().len())
+                    .sum::<usize>(),
+            )
+        })
+    });
+
+    group.bench_function("mmap", |b| {
+        b.iter(|| {
+            // SAFETY: файл бенчмарка не изменяется после подготовки
+            let mmap = unsafe { BinMmap::open(&path) }.unwrap();
+            black_box(
+                mmap.records()
+                    .map(|t| t.unwrap().description().len())
+                    .sum::<usize>(),
+            )
+        })
+    });
+
+    // SAFETY: файл бенчмарка не изменяется после подготовки
+    let mmap = unsafe { BinMmap::open(&path) }.unwrap();
+    group.bench_function("mmap_mapped", |b| {
+        b.iter(|| {
+            black_box(
+                mmap.records()
+                    .map(|t| t.unwrap().description().len())
+                    .sum::<usize>(),
+            )
+        })
+    });
+
+    group.finish();
+    drop(mmap);
+    let _ = std::fs::remove_file(&path);
+}
+
+criterion_group!(benches, bench_bin_read);
+criterion_main!(benches);
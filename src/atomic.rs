@@ -0,0 +1,231 @@
+use std::{
+    fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Максимальное количество попыток подобрать имя временного файла
+const MAX_ATTEMPTS: u32 = 100;
+
+/// Файл, который появляется по целевому пути только после успешной записи.
+/// Данные пишутся во временный файл в том же каталоге, а [`AtomicFile::commit`]
+/// сбрасывает их на диск и переименовывает временный файл в целевой.
+/// Если `commit` не был вызван, временный файл удаляется
+#[derive(Debug)]
+pub struct AtomicFile {
+    path: PathBuf,
+    temp_path: PathBuf,
+    writer: Option<BufWriter<fs::File>>,
+    no_clobber: bool,
+    committed: bool,
+}
+
+impl AtomicFile {
+    /// Создание временного файла для записи в `path`
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::create_with(path, false)
+    }
+
+    /// Создание временного файла для записи в `path`.
+    /// При `no_clobber` существующий файл не перезаписывается
+    pub fn create_with(path: impl AsRef<Path>, no_clobber: bool) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if no_clobber && path.exists() {
+            return Err(already_exists(&path));
+        }
+
+        let name = path
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Не указано имя файла"))?
+            .to_string_lossy();
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+
+        for attempt in 0..MAX_ATTEMPTS {
+            let temp_path = dir.join(format!(".{name}.{}.{attempt}.tmp", process::id()));
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&temp_path)
+            {
+                Ok(file) => {
+                    return Ok(Self {
+                        path,
+                        temp_path,
+                        writer: Some(BufWriter::new(file)),
+                        no_clobber,
+                        committed: false,
+                    });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("Не удалось создать временный файл для {}", path.display()),
+        ))
+    }
+
+    /// Целевой путь
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Сброс данных на диск и замена целевого файла временным
+    pub fn commit(mut self) -> io::Result<()> {
+        let Some(writer) = self.writer.take() else {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        };
+        let file = writer
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)?;
+        file.sync_all()?;
+        drop(file);
+
+        if self.no_clobber {
+            // Жёсткая ссылка не создаётся, если целевой файл уже появился
+            fs::hard_link(&self.temp_path, &self.path).map_err(|e| match e.kind() {
+                io::ErrorKind::AlreadyExists => already_exists(&self.path),
+                _ => e,
+            })?;
+            self.committed = true;
+            fs::remove_file(&self.temp_path)?;
+        } else {
+            fs::rename(&self.temp_path, &self.path)?;
+            self.committed = true;
+        }
+
+        sync_dir(&self.path)
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.writer.as_mut() {
+            Some(writer) => writer.write(buf),
+            None => Err(io::ErrorKind::BrokenPipe.into()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.writer.as_mut() {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            self.writer.take();
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+fn already_exists(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("Файл {} уже существует", path.display()),
+    )
+}
+
+/// Сброс на диск записи каталога о переименовании
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => fs::File::open(dir)?.sync_all(),
+        _ => fs::File::open(".")?.sync_all(),
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("atomic_{name}_{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        dir
+    }
+
+    fn get_files(dir: &Path) -> Vec<String> {
+        let mut files: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_success_commit() {
+        let dir = get_dir("commit");
+        let path = dir.join("a.csv");
+        fs::write(&path, "old").unwrap();
+
+        let mut file = AtomicFile::create(&path).unwrap();
+        file.write_all(b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        assert_eq!(get_files(&dir).len(), 2);
+
+        file.commit().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(get_files(&dir), ["a.csv"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_success_drop() {
+        let dir = get_dir("drop");
+        let path = dir.join("a.csv");
+        fs::write(&path, "old").unwrap();
+
+        let mut file = AtomicFile::create(&path).unwrap();
+        file.write_all(b"new").unwrap();
+        drop(file);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        assert_eq!(get_files(&dir), ["a.csv"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_error_no_clobber() {
+        let dir = get_dir("no_clobber");
+        let path = dir.join("a.csv");
+        fs::write(&path, "old").unwrap();
+
+        let result = AtomicFile::create_with(&path, true).unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            format!("Файл {} уже существует", path.display())
+        );
+
+        let path = dir.join("b.csv");
+        let mut file = AtomicFile::create_with(&path, true).unwrap();
+        file.write_all(b"new").unwrap();
+        fs::write(&path, "other").unwrap();
+        assert_eq!(
+            file.commit().unwrap_err().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "other");
+        assert_eq!(get_files(&dir), ["a.csv", "b.csv"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
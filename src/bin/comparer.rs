@@ -1,40 +1,108 @@
-use std::fs;
+use std::{fs, process::ExitCode};
 
+use anyhow::Context;
 use clap::Parser;
-use yandex_practicum_rust::from_read;
+use yandex_practicum_rust::{
+    Field,
+    diff::{CompareOptions, diff_with_options},
+    filter::Predicate,
+    registry::FormatRegistry,
+};
 
-/// Программа для конвертации
+/// Программа для сравнения транзакций из двух файлов
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// Файл, который будет конвертирован
+    /// Первый файл для сравнения
     #[arg(long)]
     file1: String,
 
-    /// Файл, который будет создан
+    /// Второй файл для сравнения
     #[arg(long)]
     file2: String,
+
+    /// Не учитывать порядок записей
+    #[arg(long)]
+    ignore_order: bool,
+
+    /// Поле, по которому сопоставляются записи
+    #[arg(long, default_value = "TX_ID")]
+    key: Field,
+
+    /// Поля, которые не сравниваются, через запятую
+    #[arg(long, value_delimiter = ',')]
+    ignore_field: Vec<Field>,
+
+    /// Допустимая разница TIMESTAMP в миллисекундах
+    #[arg(long, default_value_t = 0)]
+    timestamp_tolerance: u64,
+
+    /// Сравнивать только транзакции, подходящие под условие
+    #[arg(long = "where")]
+    filter: Option<Predicate>,
+}
+
+/// Код завершения, если данные не совпадают
+const EXIT_DIFFERENT: u8 = 1;
+/// Код завершения при ошибке чтения или разбора файлов
+const EXIT_ERROR: u8 = 2;
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
 }
 
-fn main() -> anyhow::Result<()> {
+/// Завершается с кодом [`EXIT_DIFFERENT`], если данные не совпадают
+fn run() -> anyhow::Result<ExitCode> {
     let args = Args::parse();
 
-    let Args { file1, file2 } = args;
+    let Args {
+        file1,
+        file2,
+        ignore_order,
+        key,
+        ignore_field,
+        timestamp_tolerance,
+        filter,
+    } = args;
 
-    let mut reader1 = fs::File::open(&file1).expect("Ошибка чтения файла file1");
-    let mut reader2 = fs::File::open(&file2).expect("Ошибка чтения файла file2");
+    let options = CompareOptions {
+        key,
+        ignore_order,
+        ignore_fields: ignore_field,
+        timestamp_tolerance,
+    };
 
-    let transactions1 = from_read(&mut reader1, &file1)?;
-    let transactions2 = from_read(&mut reader2, &file2)?;
+    let registry = FormatRegistry::default();
 
-    println!(
-        "{}",
-        if transactions1 == transactions2 {
-            "Данные совпадают"
-        } else {
-            "Данные не совпадают"
-        }
-    );
+    let read = |path: &str| -> anyhow::Result<Vec<_>> {
+        let mut reader =
+            fs::File::open(path).with_context(|| format!("Ошибка открытия файла {path}"))?;
+        registry
+            .from_read(&mut reader, path)
+            .with_context(|| format!("Ошибка чтения файла {path}"))
+    };
+    let mut transactions1 = read(&file1)?;
+    let mut transactions2 = read(&file2)?;
+
+    if let Some(filter) = filter {
+        transactions1.retain(|t| filter.matches(t));
+        transactions2.retain(|t| filter.matches(t));
+    }
+
+    let diff = diff_with_options(&transactions1, &transactions2, &options);
+    if diff.is_empty() {
+        println!("Данные совпадают");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    println!("Данные не совпадают");
+    println!("{diff}");
 
-    Ok(())
+    Ok(ExitCode::from(EXIT_DIFFERENT))
 }
@@ -1,34 +1,217 @@
-use std::fs;
+use std::{
+    fs,
+    io::{self, BufReader, BufWriter, Read, Write},
+};
 
+use anyhow::{Context, anyhow};
 use clap::Parser;
-use yandex_practicum_rust::{from_read, write_to};
+use yandex_practicum_rust::{
+    Field, Transaction,
+    atomic::AtomicFile,
+    errors::MergeError,
+    filter::Predicate,
+    merge::{MergeMode, merge},
+    registry::{FormatRegistry, RecordSource},
+    transform::{Keep, SortKey, Transforms},
+};
+
+/// Путь, означающий стандартный ввод или вывод
+const STDIO: &str = "-";
 
 /// Программа для конвертации
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// Файл, который будет конвертирован
-    #[arg(long)]
-    from: String,
+    /// Файл, который будет конвертирован, `-` для стандартного ввода.
+    /// Можно указать несколько раз или задать шаблон, например `data/*.csv`
+    #[arg(long, required = true)]
+    from: Vec<String>,
 
-    /// Файл, который будет создан
+    /// Файл, который будет создан, `-` для стандартного вывода
     #[arg(long)]
     to: String,
+
+    /// Формат всех входных файлов, например `csv` или `bin.gz`.
+    /// По умолчанию определяется для каждого файла по расширению или по содержимому
+    #[arg(long)]
+    from_format: Option<String>,
+
+    /// Формат выходных данных, например `csv` или `bin.gz`.
+    /// По умолчанию определяется по расширению
+    #[arg(long)]
+    to_format: Option<String>,
+
+    /// Не перезаписывать существующий файл
+    #[arg(long)]
+    no_clobber: bool,
+
+    /// Условие отбора транзакций, например `STATUS = SUCCESS and AMOUNT > 10000`
+    #[arg(long = "where")]
+    filter: Option<Predicate>,
+
+    /// Ключи сортировки через запятую, например `TIMESTAMP:desc,TX_ID`
+    #[arg(long, value_delimiter = ',')]
+    sort_by: Vec<SortKey>,
+
+    /// Поля через запятую, по которым удаляются повторяющиеся транзакции
+    #[arg(long, value_delimiter = ',')]
+    dedupe_by: Vec<Field>,
+
+    /// Какую из повторяющихся транзакций оставить: `first` или `last`
+    #[arg(long, default_value = "first")]
+    keep: Keep,
+
+    /// Количество пропускаемых транзакций
+    #[arg(long, default_value_t = 0)]
+    offset: usize,
+
+    /// Максимальное количество транзакций
+    #[arg(long)]
+    limit: Option<usize>,
+
+    /// Объединение нескольких входных файлов: `concat` или `timestamp`
+    #[arg(long = "merge", default_value = "concat")]
+    merge_mode: MergeMode,
 }
 
+/// Потоковое чтение транзакций
+type Records<'a> = Box<dyn Iterator<Item = anyhow::Result<Transaction>> + 'a>;
+
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
-    let Args { from, to } = args;
+    let Args {
+        from,
+        to,
+        from_format,
+        to_format,
+        no_clobber,
+        filter,
+        sort_by,
+        dedupe_by,
+        keep,
+        offset,
+        limit,
+        merge_mode,
+    } = args;
+
+    let transforms = Transforms {
+        sort_by,
+        dedupe_by,
+        keep,
+        offset,
+        limit,
+    };
 
-    let mut reader = fs::File::open(&from).expect("Ошибка чтения файла");
+    let registry = FormatRegistry::default();
 
-    let transactions = from_read(&mut reader, &from)?;
+    let from_format = from_format
+        .map(|name| registry.parse_format(&name))
+        .transpose()?;
+    let to_format = match to_format {
+        Some(name) => registry.parse_format(&name)?,
+        None if to == STDIO => anyhow::bail!("Для вывода в stdout нужно указать --to-format"),
+        None => registry.file_format(&to)?,
+    };
 
-    let mut writer = fs::File::create(&to).expect("Ошибка создания файла");
+    let open = |path: &str| -> anyhow::Result<RecordSource<'_>> {
+        let reader: Box<dyn Read> = match path {
+            STDIO => Box::new(io::stdin().lock()),
+            path => Box::new(BufReader::new(
+                fs::File::open(path).with_context(|| format!("Ошибка чтения файла {path}"))?,
+            )),
+        };
+        let format = from_format.or_else(|| registry.file_format(path).ok());
+        Ok(registry.open(reader, format)?)
+    };
 
-    write_to(&mut writer, &transactions, &to)?;
+    let inputs = expand_inputs(&from)?;
+    let mut sources = inputs
+        .iter()
+        .map(|input| open(input))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let records: Records<'_> = match sources.as_mut_slice() {
+        [source] if merge_mode == MergeMode::Concat => Box::new(source.records().map(|r| Ok(r?))),
+        sources => {
+            let sources = sources.iter_mut().zip(&inputs).map(|(source, input)| {
+                source.records().map(move |record| {
+                    record.with_context(|| format!("Ошибка чтения файла {input}"))
+                })
+            });
+            // Конфликты проверяются до фильтра, чтобы он их не скрывал
+            Box::new(merge(sources, merge_mode).map(|record| {
+                record.map_err(|e| match e.downcast::<MergeError>() {
+                    Ok(MergeError::Conflict {
+                        tx_id,
+                        first,
+                        second,
+                    }) => anyhow!(
+                        "Транзакция {tx_id} отличается в файлах {} и {}",
+                        inputs[first],
+                        inputs[second]
+                    ),
+                    Ok(MergeError::Unsorted {
+                        tx_id,
+                        source_index,
+                    }) => anyhow!(
+                        "Транзакция {tx_id} в файле {} нарушает порядок по TIMESTAMP",
+                        inputs[source_index]
+                    ),
+                    Err(e) => e,
+                })
+            }))
+        }
+    };
+    let records = records.filter(|record| match (record, &filter) {
+        (Ok(t), Some(filter)) => filter.matches(t),
+        _ => true,
+    });
+    let records = transforms.apply_iter(records);
 
-    println!("Конвертация успешно завершена!");
+    // Файл заменяется только после успешной записи всех данных
+    let mut file = match to.as_str() {
+        STDIO => None,
+        path => Some(AtomicFile::create_with(path, no_clobber).context("Ошибка создания файла")?),
+    };
+    let mut writer: Box<dyn Write> = match file.as_mut() {
+        Some(file) => Box::new(file),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    registry.write_records(&mut writer, to_format, records)?;
+    writer.flush()?;
+    drop(writer);
+
+    if let Some(file) = file {
+        file.commit().context("Ошибка записи файла")?;
+    }
+
+    eprintln!("Конвертация успешно завершена!");
     Ok(())
 }
+
+/// Пути входных файлов.
+/// Шаблоны с `*`, `?` или `[` раскрываются в список файлов в алфавитном порядке.
+/// Стандартный ввод читается один раз, поэтому `-` можно указать только однажды
+fn expand_inputs(from: &[String]) -> anyhow::Result<Vec<String>> {
+    let mut inputs = Vec::new();
+    for pattern in from {
+        if pattern == STDIO && inputs.iter().any(|input| input == STDIO) {
+            anyhow::bail!("Стандартный ввод `-` указан в --from несколько раз");
+        }
+        if pattern == STDIO || !pattern.contains(['*', '?', '[']) {
+            inputs.push(pattern.clone());
+            continue;
+        }
+
+        let paths = glob::glob(pattern)
+            .with_context(|| format!("Некорректный шаблон {pattern}"))?
+            .map(|path| Ok(path?.to_string_lossy().into_owned()))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if paths.is_empty() {
+            anyhow::bail!("Нет файлов по шаблону {pattern}");
+        }
+        inputs.extend(paths);
+    }
+    Ok(inputs)
+}
@@ -0,0 +1,234 @@
+use crate::{Field, Status, Transaction, TxType, errors::TransactionBuildError};
+
+/// Построитель транзакции с проверкой заполненности полей
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TransactionBuilder {
+    tx_id: Option<u64>,
+    tx_type: Option<TxType>,
+    from_user_id: Option<u64>,
+    to_user_id: Option<u64>,
+    amount: Option<u64>,
+    timestamp: Option<i64>,
+    status: Option<Status>,
+    description: Option<String>,
+}
+
+impl TransactionBuilder {
+    /// Создание пустого построителя
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Уникальный идентификатор транзакции
+    pub fn tx_id(mut self, value: u64) -> Self {
+        self.tx_id = Some(value);
+        self
+    }
+
+    /// Тип транзакции
+    pub fn tx_type(mut self, value: TxType) -> Self {
+        self.tx_type = Some(value);
+        self
+    }
+
+    /// Идентификатор пользователя-отправителя
+    pub fn from_user_id(mut self, value: u64) -> Self {
+        self.from_user_id = Some(value);
+        self
+    }
+
+    /// Идентификатор пользователя-получателя
+    pub fn to_user_id(mut self, value: u64) -> Self {
+        self.to_user_id = Some(value);
+        self
+    }
+
+    /// Сумма транзакции в наименьших единицах валюты
+    pub fn amount(mut self, value: u64) -> Self {
+        self.amount = Some(value);
+        self
+    }
+
+    /// Время совершения транзакции в формате Unix-времени
+    pub fn timestamp(mut self, value: i64) -> Self {
+        self.timestamp = Some(value);
+        self
+    }
+
+    /// Статус транзакции
+    pub fn status(mut self, value: Status) -> Self {
+        self.status = Some(value);
+        self
+    }
+
+    /// Текстовое описание транзакции
+    pub fn description(mut self, value: impl Into<String>) -> Self {
+        self.description = Some(value.into());
+        self
+    }
+
+    /// Заполнение поля из строкового представления
+    pub fn value(mut self, field: &Field, value: &str) -> Result<Self, TransactionBuildError> {
+        let invalid = || TransactionBuildError::InvalidField {
+            field: field.clone(),
+        };
+
+        match field {
+            Field::TxId => self.tx_id = Some(value.parse().map_err(|_| invalid())?),
+            Field::TxType => self.tx_type = Some(value.parse().map_err(|_| invalid())?),
+            Field::FromUserId => self.from_user_id = Some(value.parse().map_err(|_| invalid())?),
+            Field::ToUserId => self.to_user_id = Some(value.parse().map_err(|_| invalid())?),
+            Field::Amount => self.amount = Some(value.parse().map_err(|_| invalid())?),
+            Field::Timestamp => self.timestamp = Some(value.parse().map_err(|_| invalid())?),
+            Field::Status => self.status = Some(value.parse().map_err(|_| invalid())?),
+            Field::Description => self.description = Some(value.to_string()),
+        }
+
+        Ok(self)
+    }
+
+    /// Проверка, заполнено ли поле
+    pub fn is_set(&self, field: &Field) -> bool {
+        match field {
+            Field::TxId => self.tx_id.is_some(),
+            Field::TxType => self.tx_type.is_some(),
+            Field::FromUserId => self.from_user_id.is_some(),
+            Field::ToUserId => self.to_user_id.is_some(),
+            Field::Amount => self.amount.is_some(),
+            Field::Timestamp => self.timestamp.is_some(),
+            Field::Status => self.status.is_some(),
+            Field::Description => self.description.is_some(),
+        }
+    }
+
+    /// Создание транзакции
+    pub fn build(self) -> Result<Transaction, TransactionBuildError> {
+        let missing = |field: Field| TransactionBuildError::MissingField { field };
+
+        Ok(Transaction {
+            tx_id: self.tx_id.ok_or_else(|| missing(Field::TxId))?,
+            tx_type: self.tx_type.ok_or_else(|| missing(Field::TxType))?,
+            from_user_id: self
+                .from_user_id
+                .ok_or_else(|| missing(Field::FromUserId))?,
+            to_user_id: self.to_user_id.ok_or_else(|| missing(Field::ToUserId))?,
+            amount: self.amount.ok_or_else(|| missing(Field::Amount))?,
+            timestamp: self.timestamp.ok_or_else(|| missing(Field::Timestamp))?,
+            status: self.status.ok_or_else(|| missing(Field::Status))?,
+            description: self
+                .description
+                .ok_or_else(|| missing(Field::Description))?,
+        })
+    }
+}
+
+impl From<Transaction> for TransactionBuilder {
+    fn from(value: Transaction) -> Self {
+        Self {
+            tx_id: Some(value.tx_id),
+            tx_type: Some(value.tx_type),
+            from_user_id: Some(value.from_user_id),
+            to_user_id: Some(value.to_user_id),
+            amount: Some(value.amount),
+            timestamp: Some(value.timestamp),
+            status: Some(value.status),
+            description: Some(value.description),
+        }
+    }
+}
+
+impl TryFrom<TransactionBuilder> for Transaction {
+    type Error = TransactionBuildError;
+
+    fn try_from(value: TransactionBuilder) -> Result<Self, Self::Error> {
+        value.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_builder() -> TransactionBuilder {
+        Transaction::builder()
+            .tx_id(1)
+            .tx_type(TxType::Transfer)
+            .from_user_id(1)
+            .to_user_id(2)
+            .amount(1000)
+            .timestamp(1633036860000)
+            .status(Status::Pending)
+            .description("record 1")
+    }
+
+    #[test]
+    fn test_success_build() {
+        let transaction = get_builder().build().unwrap();
+        assert_eq!(transaction.tx_id(), 1);
+        assert_eq!(transaction.tx_type(), TxType::Transfer);
+        assert_eq!(transaction.from_user_id(), 1);
+        assert_eq!(transaction.to_user_id(), 2);
+        assert_eq!(transaction.amount(), 1000);
+        assert_eq!(transaction.timestamp(), 1633036860000);
+        assert_eq!(transaction.status(), Status::Pending);
+        assert_eq!(transaction.description(), "record 1");
+    }
+
+    #[test]
+    fn test_success_value() {
+        let transaction = Transaction::builder()
+            .value(&Field::TxId, "1")
+            .and_then(|b| b.value(&Field::TxType, "TRANSFER"))
+            .and_then(|b| b.value(&Field::FromUserId, "1"))
+            .and_then(|b| b.value(&Field::ToUserId, "2"))
+            .and_then(|b| b.value(&Field::Amount, "1000"))
+            .and_then(|b| b.value(&Field::Timestamp, "1633036860000"))
+            .and_then(|b| b.value(&Field::Status, "PENDING"))
+            .and_then(|b| b.value(&Field::Description, "record 1"))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(transaction, get_builder().build().unwrap());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let transaction = get_builder().build().unwrap();
+        let builder = TransactionBuilder::from(transaction.clone());
+        assert_eq!(Transaction::try_from(builder).unwrap(), transaction);
+
+        let mut modified = transaction.clone();
+        modified.set_amount(5);
+        modified.set_description("changed");
+        assert_eq!(
+            modified,
+            TransactionBuilder::from(transaction)
+                .amount(5)
+                .description("changed")
+                .build()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_error_missing_field() {
+        let result = Transaction::builder().tx_id(1).build().unwrap_err();
+        assert_eq!(result.to_string(), "Отсутствует поле TX_TYPE");
+
+        let result = Transaction::try_from(TransactionBuilder::new()).unwrap_err();
+        assert_eq!(result.to_string(), "Отсутствует поле TX_ID");
+    }
+
+    #[test]
+    fn test_error_invalid_field() {
+        let result = Transaction::builder()
+            .value(&Field::Amount, "-1")
+            .unwrap_err();
+        assert_eq!(result.to_string(), "Некорректное значение поля AMOUNT");
+
+        let result = Transaction::builder()
+            .value(&Field::Status, "DONE")
+            .unwrap_err();
+        assert_eq!(result.to_string(), "Некорректное значение поля STATUS");
+    }
+}
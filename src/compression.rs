@@ -0,0 +1,194 @@
+use std::io::{self, Read, Write};
+
+use strum::{Display, EnumString};
+
+use crate::errors::ParserError;
+
+/// Сжатие файла, определяемое по расширению после расширения формата,
+/// например `transactions.csv.gz`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Display, EnumString)]
+pub enum Compression {
+    /// Без сжатия
+    #[default]
+    #[strum(serialize = "")]
+    None,
+    /// gzip, требует функцию `gzip`
+    #[strum(serialize = "gz")]
+    Gzip,
+    /// zstd, требует функцию `zstd`
+    #[strum(serialize = "zst")]
+    Zstd,
+}
+
+impl Compression {
+    /// Определение сжатия по пути к файлу.
+    /// Возвращает сжатие и путь без расширения сжатия
+    pub fn from_path(path: &str) -> (Self, &str) {
+        match path.rsplit_once('.') {
+            Some((stem, ext)) if !ext.is_empty() => match ext.parse::<Compression>() {
+                Ok(compression) => (compression, stem),
+                Err(_) => (Self::None, path),
+            },
+            _ => (Self::None, path),
+        }
+    }
+
+    /// Определение сжатия по началу содержимого файла
+    pub fn detect(data: &[u8]) -> Self {
+        if data.starts_with(&[0x1f, 0x8b]) {
+            Self::Gzip
+        } else if data.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Self::Zstd
+        } else {
+            Self::None
+        }
+    }
+
+    /// Потоковая распаковка
+    pub fn reader<R: Read>(self, reader: R) -> Result<CompressedReader<R>, ParserError> {
+        Ok(match self {
+            Self::None => CompressedReader::Plain(reader),
+            #[cfg(feature = "gzip")]
+            Self::Gzip => CompressedReader::Gzip(flate2::read::MultiGzDecoder::new(reader)),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => {
+                CompressedReader::Zstd(zstd::Decoder::new(reader).map_err(ParserError::Read)?)
+            }
+            #[allow(unreachable_patterns)]
+            _ => return Err(ParserError::UnsupportedCompression(self)),
+        })
+    }
+
+    /// Потоковое сжатие
+    pub fn writer<W: Write>(self, writer: W) -> Result<CompressedWriter<W>, ParserError> {
+        Ok(match self {
+            Self::None => CompressedWriter::Plain(writer),
+            #[cfg(feature = "gzip")]
+            Self::Gzip => CompressedWriter::Gzip(flate2::write::GzEncoder::new(
+                writer,
+                flate2::Compression::default(),
+            )),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => CompressedWriter::Zstd(
+                zstd::Encoder::new(writer, 0).map_err(|e| ParserError::Write(e.into()))?,
+            ),
+            #[allow(unreachable_patterns)]
+            _ => return Err(ParserError::UnsupportedCompression(self)),
+        })
+    }
+}
+
+/// Чтение с распаковкой
+pub enum CompressedReader<R: Read> {
+    /// Без сжатия
+    Plain(R),
+    /// gzip
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::read::MultiGzDecoder<R>),
+    /// zstd
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Decoder<'static, io::BufReader<R>>),
+}
+
+impl<R: Read> Read for CompressedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(reader) => reader.read(buf),
+            #[cfg(feature = "gzip")]
+            Self::Gzip(reader) => reader.read(buf),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(reader) => reader.read(buf),
+        }
+    }
+}
+
+/// Запись со сжатием.
+/// Поток сжатия завершается только в [`CompressedWriter::finish`]
+pub enum CompressedWriter<W: Write> {
+    /// Без сжатия
+    Plain(W),
+    /// gzip
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<W>),
+    /// zstd
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> CompressedWriter<W> {
+    /// Завершение потока сжатия и получение исходного потока
+    pub fn finish(self) -> io::Result<W> {
+        let mut writer = match self {
+            Self::Plain(writer) => writer,
+            #[cfg(feature = "gzip")]
+            Self::Gzip(writer) => writer.finish()?,
+            #[cfg(feature = "zstd")]
+            Self::Zstd(writer) => writer.finish()?,
+        };
+        writer.flush()?;
+        Ok(writer)
+    }
+}
+
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(writer) => writer.write(buf),
+            #[cfg(feature = "gzip")]
+            Self::Gzip(writer) => writer.write(buf),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(writer) => writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(writer) => writer.flush(),
+            #[cfg(feature = "gzip")]
+            Self::Gzip(writer) => writer.flush(),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(writer) => writer.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(
+            Compression::from_path("a.csv.gz"),
+            (Compression::Gzip, "a.csv")
+        );
+        assert_eq!(
+            Compression::from_path("dir.v2/a.txt.zst"),
+            (Compression::Zstd, "dir.v2/a.txt")
+        );
+        assert_eq!(
+            Compression::from_path("a.bin"),
+            (Compression::None, "a.bin")
+        );
+        assert_eq!(Compression::from_path("a."), (Compression::None, "a."));
+    }
+
+    #[cfg(all(feature = "gzip", feature = "zstd"))]
+    #[test]
+    fn test_round_trip() {
+        for compression in [Compression::None, Compression::Gzip, Compression::Zstd] {
+            let mut writer = compression.writer(Vec::new()).unwrap();
+            writer.write_all(b"data ".repeat(100).as_slice()).unwrap();
+            let data = writer.finish().unwrap();
+            assert_eq!(Compression::detect(&data), compression);
+
+            let mut result = Vec::new();
+            compression
+                .reader(data.as_slice())
+                .unwrap()
+                .read_to_end(&mut result)
+                .unwrap();
+            assert_eq!(result, b"data ".repeat(100));
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{Field, Transaction};
+
+/// Настройки сравнения наборов транзакций
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompareOptions {
+    /// Поле, по которому сопоставляются записи
+    pub key: Field,
+    /// Не считать отличием разный порядок записей
+    pub ignore_order: bool,
+    /// Поля, которые не сравниваются
+    pub ignore_fields: Vec<Field>,
+    /// Допустимая разница `TIMESTAMP` в миллисекундах
+    pub timestamp_tolerance: u64,
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        Self {
+            key: Field::TxId,
+            ignore_order: false,
+            ignore_fields: Vec::new(),
+            timestamp_tolerance: 0,
+        }
+    }
+}
+
+/// Изменение значения поля транзакции
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    /// Изменённое поле
+    pub field: Field,
+    /// Значение в первом наборе
+    pub old: String,
+    /// Значение во втором наборе
+    pub new: String,
+}
+
+/// Отличие между наборами транзакций
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// Транзакция есть только во втором наборе
+    Added(Transaction),
+    /// Транзакция есть только в первом наборе
+    Removed(Transaction),
+    /// Транзакция есть в обоих наборах, но значения полей отличаются
+    Changed {
+        /// Значение ключевого поля
+        key: String,
+        /// Изменённые поля в порядке [`Field`]
+        fields: Vec<FieldChange>,
+    },
+}
+
+/// Результат сравнения двух наборов транзакций
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diff {
+    /// Поле, по которому сопоставлялись записи
+    pub key: Field,
+    /// Отличия: сначала удалённые и изменённые в порядке первого набора,
+    /// затем добавленные в порядке второго
+    pub changes: Vec<Change>,
+    /// Совпадающие записи расположены в разном порядке
+    pub reordered: bool,
+}
+
+impl Diff {
+    /// Совпадают ли наборы
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && !self.reordered
+    }
+
+    /// Количество добавленных транзакций
+    pub fn added(&self) -> usize {
+        self.count(|change| matches!(change, Change::Added(_)))
+    }
+
+    /// Количество удалённых транзакций
+    pub fn removed(&self) -> usize {
+        self.count(|change| matches!(change, Change::Removed(_)))
+    }
+
+    /// Количество изменённых транзакций
+    pub fn changed(&self) -> usize {
+        self.count(|change| matches!(change, Change::Changed { .. }))
+    }
+
+    fn count(&self, predicate: impl Fn(&Change) -> bool) -> usize {
+        self.changes
+            .iter()
+            .filter(|change| predicate(change))
+            .count()
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = &self.key;
+        for change in &self.changes {
+            match change {
+                Change::Added(t) => writeln!(f, "+ {key} {}", t.get_value(key))?,
+                Change::Removed(t) => writeln!(f, "- {key} {}", t.get_value(key))?,
+                Change::Changed { key: value, fields } => {
+                    writeln!(f, "~ {key} {value}")?;
+                    for change in fields {
+                        writeln!(
+                            f,
+                            "    {}: {:?} -> {:?}",
+                            change.field, change.old, change.new
+                        )?;
+                    }
+                }
+            }
+        }
+        if self.reordered {
+            writeln!(f, "Порядок записей отличается")?;
+        }
+        write!(
+            f,
+            "Добавлено: {}, удалено: {}, изменено: {}",
+            self.added(),
+            self.removed(),
+            self.changed()
+        )
+    }
+}
+
+/// Сравнение наборов транзакций с настройками по умолчанию
+pub fn diff(old: &[Transaction], new: &[Transaction]) -> Diff {
+    diff_with_options(old, new, &CompareOptions::default())
+}
+
+/// Сравнение наборов транзакций с сопоставлением записей по ключевому полю.
+/// Повторяющиеся ключи сопоставляются в порядке следования
+pub fn diff_with_options(
+    old: &[Transaction],
+    new: &[Transaction],
+    options: &CompareOptions,
+) -> Diff {
+    let key = &options.key;
+
+    let mut positions: HashMap<String, Vec<usize>> = HashMap::new();
+    for (position, t) in new.iter().enumerate().rev() {
+        positions
+            .entry(t.get_value(key))
+            .or_default()
+            .push(position);
+    }
+
+    let mut matched = vec![false; new.len()];
+    let mut order = Vec::new();
+    let mut changes = Vec::new();
+
+    for t in old {
+        let value = t.get_value(key);
+        let Some(position) = positions.get_mut(&value).and_then(Vec::pop) else {
+            changes.push(Change::Removed(t.clone()));
+            continue;
+        };
+        matched[position] = true;
+        order.push(position);
+
+        let fields = diff_fields(t, &new[position], options);
+        if !fields.is_empty() {
+            changes.push(Change::Changed { key: value, fields });
+        }
+    }
+
+    changes.extend(
+        new.iter()
+            .zip(matched)
+            .filter(|(_, matched)| !matched)
+            .map(|(t, _)| Change::Added(t.clone())),
+    );
+
+    Diff {
+        key: key.clone(),
+        changes,
+        reordered: !options.ignore_order && !order.is_sorted(),
+    }
+}
+
+/// Поля, значения которых отличаются с учётом настроек сравнения
+fn diff_fields(old: &Transaction, new: &Transaction, options: &CompareOptions) -> Vec<FieldChange> {
+    Field::get_all()
+        .into_iter()
+        .filter(|field| !options.ignore_fields.contains(field))
+        .filter(|field| {
+            *field != Field::Timestamp
+                || old.timestamp.abs_diff(new.timestamp) > options.timestamp_tolerance
+        })
+        .filter_map(|field| {
+            let (old, new) = (old.get_value(&field), new.get_value(&field));
+            (old != new).then_some(FieldChange { field, old, new })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Status;
+
+    fn get_transaction(tx_id: u64, amount: u64) -> Transaction {
+        Transaction {
+            tx_id,
+            amount,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_success_diff() {
+        let old = [get_transaction(1, 100), get_transaction(2, 200)];
+        assert!(diff(&old, &old).is_empty());
+
+        let old = [
+            get_transaction(1, 100),
+            get_transaction(2, 200),
+            get_transaction(3, 300),
+        ];
+        let mut changed = get_transaction(2, 250);
+        changed.status = Status::Failure;
+        let new = [get_transaction(4, 400), changed, get_transaction(1, 100)];
+
+        let result = diff(&old, &new);
+        assert_eq!(
+            result.changes,
+            [
+                Change::Changed {
+                    key: "2".to_string(),
+                    fields: vec![
+                        FieldChange {
+                            field: Field::Amount,
+                            old: "200".to_string(),
+                            new: "250".to_string(),
+                        },
+                        FieldChange {
+                            field: Field::Status,
+                            old: "SUCCESS".to_string(),
+                            new: "FAILURE".to_string(),
+                        },
+                    ],
+                },
+                Change::Removed(get_transaction(3, 300)),
+                Change::Added(get_transaction(4, 400)),
+            ]
+        );
+        assert_eq!(
+            result.to_string(),
+            "~ TX_ID 2\n    AMOUNT: \"200\" -> \"250\"\n    STATUS: \"SUCCESS\" -> \"FAILURE\"\n\
+             - TX_ID 3\n+ TX_ID 4\nПорядок записей отличается\nДобавлено: 1, удалено: 1, изменено: 1"
+        );
+    }
+
+    #[test]
+    fn test_success_diff_duplicates() {
+        let old = [get_transaction(1, 100), get_transaction(1, 200)];
+        let new = [
+            get_transaction(1, 100),
+            get_transaction(1, 300),
+            get_transaction(1, 400),
+        ];
+
+        let result = diff(&old, &new);
+        assert!(!result.reordered);
+        assert_eq!(result.changed(), 1);
+        assert_eq!(result.added(), 1);
+        assert_eq!(result.changes[1], Change::Added(get_transaction(1, 400)));
+    }
+
+    #[test]
+    fn test_success_diff_with_options() {
+        let old = [get_transaction(1, 100), get_transaction(2, 200)];
+        let mut new = [get_transaction(2, 200), get_transaction(1, 100)];
+        new[0].timestamp = 5;
+        new[1].description = " a ".to_string();
+
+        let result = diff(&old, &new);
+        assert!(result.reordered);
+        assert_eq!(result.changed(), 2);
+
+        let options = CompareOptions {
+            ignore_order: true,
+            ignore_fields: vec![Field::Description],
+            timestamp_tolerance: 5,
+            ..Default::default()
+        };
+        assert!(diff_with_options(&old, &new, &options).is_empty());
+
+        let options = CompareOptions {
+            timestamp_tolerance: 4,
+            ..options
+        };
+        assert_eq!(diff_with_options(&old, &new, &options).changed(), 1);
+
+        let options = CompareOptions {
+            key: Field::Amount,
+            ignore_order: true,
+            ..Default::default()
+        };
+        let result = diff_with_options(&old, &[get_transaction(3, 100)], &options);
+        assert_eq!(
+            result.to_string(),
+            "~ AMOUNT 100\n    TX_ID: \"1\" -> \"3\"\n- AMOUNT 200\nДобавлено: 0, удалено: 1, изменено: 1"
+        );
+    }
+}
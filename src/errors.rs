@@ -1,13 +1,22 @@
+use std::io;
+
 use thiserror::Error;
 
-use crate::parsers::{bin::error::BinError, csv::error::CsvError, txt::error::TxtError};
+use crate::{
+    Field,
+    compression::Compression,
+    filter::Operator,
+    parsers::{
+        bin::error::BinError, csv::error::CsvError, json::error::JsonError, txt::error::TxtError,
+    },
+};
 
 /// Ошибка записи
 #[derive(Debug, Error)]
 pub enum WriteError {
     /// Ошибка записи
-    #[error("Ошибка записи")]
-    Write,
+    #[error("Ошибка записи: {0}")]
+    Write(#[from] io::Error),
 }
 
 /// Ошибка работы парсера
@@ -16,6 +25,21 @@ pub enum ParserError {
     /// Неизвестное расширение файла
     #[error("Неизвестное расширение файла")]
     UnknownExt,
+    /// Неизвестное название формата
+    #[error("Неизвестный формат {0}")]
+    UnknownFormat(String),
+    /// Содержимое файла подходит под несколько форматов
+    #[error("Не удалось однозначно определить формат файла")]
+    AmbiguousFormat,
+    /// Содержимое файла не подходит ни под один формат
+    #[error("Не удалось определить формат файла по содержимому")]
+    UnknownContent,
+    /// Сжатие не поддерживается, так как отключена соответствующая функция пакета
+    #[error("Сжатие {0} не поддерживается в этой сборке")]
+    UnsupportedCompression(Compression),
+    /// Ошибка чтения
+    #[error("Ошибка чтения: {0}")]
+    Read(#[source] io::Error),
     /// Ошибка csv парсера
     #[error("Ошибка csv парсера: {0}")]
     Csv(#[from] CsvError),
@@ -25,7 +49,127 @@ pub enum ParserError {
     /// Ошибка bin парсера
     #[error("Ошибка bin парсера: {0}")]
     Bin(#[from] BinError),
+    /// Ошибка json или jsonl парсера
+    #[error("Ошибка json парсера: {0}")]
+    Json(#[from] JsonError),
     /// Ошибка записи
     #[error("Ошибка записи: {0}")]
     Write(#[from] WriteError),
+    /// Ошибка парсера, зарегистрированного вне библиотеки
+    #[error("Ошибка парсера: {0}")]
+    Custom(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// Ошибка построения транзакции
+#[derive(Debug, Error)]
+pub enum TransactionBuildError {
+    /// Не заполнено обязательное поле
+    #[error("Отсутствует поле {field}")]
+    MissingField {
+        /// Поле
+        field: Field,
+    },
+    /// Некорректное значение поля
+    #[error("Некорректное значение поля {field}")]
+    InvalidField {
+        /// Поле
+        field: Field,
+    },
+}
+
+/// Ошибка разбора выражения фильтра
+#[derive(Debug, Error, PartialEq)]
+pub enum FilterError {
+    /// Недопустимый символ
+    #[error("Неожиданный символ {char:?} в позиции {position}")]
+    UnexpectedChar {
+        /// Смещение в байтах
+        position: usize,
+        /// Символ
+        char: char,
+    },
+    /// Строка не закрыта кавычкой
+    #[error("Незакрытая кавычка в позиции {position}")]
+    UnterminatedString {
+        /// Смещение открывающей кавычки в байтах
+        position: usize,
+    },
+    /// Лексема в неподходящем месте
+    #[error("Неожиданное {token:?} в позиции {position}")]
+    UnexpectedToken {
+        /// Смещение в байтах
+        position: usize,
+        /// Лексема
+        token: String,
+    },
+    /// Выражение закончилось раньше времени
+    #[error("Неожиданный конец выражения")]
+    UnexpectedEnd,
+    /// Неизвестное имя поля
+    #[error("Неизвестное поле {name} в позиции {position}")]
+    UnknownField {
+        /// Смещение в байтах
+        position: usize,
+        /// Имя поля
+        name: String,
+    },
+    /// Значение не подходит для поля
+    #[error("Некорректное значение {value:?} для поля {field} в позиции {position}")]
+    InvalidValue {
+        /// Смещение в байтах
+        position: usize,
+        /// Поле
+        field: Field,
+        /// Значение
+        value: String,
+    },
+    /// Оператор сравнения не применим к полю
+    #[error("Оператор {operator} неприменим к полю {field} в позиции {position}")]
+    InvalidOperator {
+        /// Смещение в байтах
+        position: usize,
+        /// Поле
+        field: Field,
+        /// Оператор
+        operator: Operator,
+    },
+    /// Превышена допустимая глубина выражения
+    #[error("Превышена глубина выражения {limit} в позиции {position}")]
+    TooDeep {
+        /// Смещение в байтах
+        position: usize,
+        /// Ограничение
+        limit: usize,
+    },
+}
+
+/// Ошибка разбора настроек преобразования
+#[derive(Debug, Error, PartialEq)]
+pub enum TransformError {
+    /// Некорректный ключ сортировки
+    #[error("Некорректный ключ сортировки {0}, ожидается FIELD, FIELD:asc или FIELD:desc")]
+    InvalidSortKey(String),
+}
+
+/// Ошибка объединения наборов транзакций
+#[derive(Debug, Error, PartialEq)]
+pub enum MergeError {
+    /// Транзакция с одним идентификатором отличается в разных записях
+    #[error("Транзакция {tx_id} отличается в источниках {first} и {second}")]
+    Conflict {
+        /// Уникальный идентификатор транзакции
+        tx_id: u64,
+        /// Номер источника, в котором транзакция встретилась впервые
+        first: usize,
+        /// Номер источника с отличающейся транзакцией
+        second: usize,
+    },
+    /// Источник не упорядочен по `TIMESTAMP`
+    #[error("Транзакция {tx_id} в источнике {source_index} нарушает порядок по TIMESTAMP")]
+    Unsorted {
+        /// Уникальный идентификатор транзакции
+        tx_id: u64,
+        /// Номер источника
+        source_index: usize,
+    },
 }
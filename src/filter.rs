@@ -0,0 +1,669 @@
+use std::{cmp::Ordering, str::FromStr};
+
+use strum::Display;
+
+use crate::{Field, Status, Transaction, TxType, errors::FilterError};
+
+/// Оператор сравнения
+#[derive(Debug, Clone, Copy, PartialEq, Display)]
+pub enum Operator {
+    /// Равно
+    #[strum(serialize = "=")]
+    Eq,
+    /// Не равно
+    #[strum(serialize = "!=")]
+    Ne,
+    /// Меньше
+    #[strum(serialize = "<")]
+    Lt,
+    /// Меньше или равно
+    #[strum(serialize = "<=")]
+    Le,
+    /// Больше
+    #[strum(serialize = ">")]
+    Gt,
+    /// Больше или равно
+    #[strum(serialize = ">=")]
+    Ge,
+}
+
+impl Operator {
+    fn apply(self, ordering: Option<Ordering>) -> bool {
+        match self {
+            Self::Eq => ordering == Some(Ordering::Equal),
+            Self::Ne => ordering != Some(Ordering::Equal),
+            Self::Lt => ordering == Some(Ordering::Less),
+            Self::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            Self::Gt => ordering == Some(Ordering::Greater),
+            Self::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        }
+    }
+}
+
+/// Значение поля в выражении фильтра
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Идентификаторы, сумма и время
+    Number(i128),
+    /// Тип транзакции
+    TxType(TxType),
+    /// Статус транзакции
+    Status(Status),
+    /// Описание
+    Text(String),
+}
+
+impl Value {
+    /// Значение поля транзакции
+    pub fn of(t: &Transaction, field: &Field) -> Self {
+        match field {
+            Field::TxId => Self::Number(t.tx_id.into()),
+            Field::TxType => Self::TxType(t.tx_type),
+            Field::FromUserId => Self::Number(t.from_user_id.into()),
+            Field::ToUserId => Self::Number(t.to_user_id.into()),
+            Field::Amount => Self::Number(t.amount.into()),
+            Field::Timestamp => Self::Number(t.timestamp.into()),
+            Field::Status => Self::Status(t.status),
+            Field::Description => Self::Text(t.description.clone()),
+        }
+    }
+
+    /// Сравнение значений одного вида. Тип и статус транзакции только проверяются на равенство
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => Some(a.cmp(b)),
+            (Self::Text(a), Self::Text(b)) => Some(a.cmp(b)),
+            (Self::TxType(a), Self::TxType(b)) => (a == b).then_some(Ordering::Equal),
+            (Self::Status(a), Self::Status(b)) => (a == b).then_some(Ordering::Equal),
+            _ => None,
+        }
+    }
+}
+
+/// Условие отбора транзакций.
+/// Разбирается из выражения вида `STATUS = SUCCESS and AMOUNT > 10000 and TX_TYPE in (DEPOSIT, TRANSFER)`.
+/// Поддерживаются операторы `=`, `!=`, `<`, `<=`, `>`, `>=`, `in`, `not in`, `and`, `or`, `not`
+/// и скобки. `TIMESTAMP` можно задать датой `2024-03-01` или `2024-03-01T12:00:00` в UTC
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    /// Сравнение поля со значением
+    Compare {
+        /// Поле
+        field: Field,
+        /// Оператор
+        operator: Operator,
+        /// Значение
+        value: Value,
+    },
+    /// Значение поля входит в список
+    In {
+        /// Поле
+        field: Field,
+        /// Допустимые значения
+        values: Vec<Value>,
+    },
+    /// Отрицание
+    Not(Box<Predicate>),
+    /// Выполняются все условия
+    And(Vec<Predicate>),
+    /// Выполняется хотя бы одно условие
+    Or(Vec<Predicate>),
+}
+
+impl Predicate {
+    /// Подходит ли транзакция под условие
+    pub fn matches(&self, t: &Transaction) -> bool {
+        match self {
+            Self::Compare {
+                field,
+                operator,
+                value,
+            } => operator.apply(Value::of(t, field).compare(value)),
+            Self::In { field, values } => {
+                let actual = Value::of(t, field);
+                values
+                    .iter()
+                    .any(|value| actual.compare(value) == Some(Ordering::Equal))
+            }
+            Self::Not(predicate) => !predicate.matches(t),
+            Self::And(predicates) => predicates.iter().all(|p| p.matches(t)),
+            Self::Or(predicates) => predicates.iter().any(|p| p.matches(t)),
+        }
+    }
+
+    /// Объединение условий через `and`. Условие добавляется в конец цепочки `and`
+    pub fn and(self, other: Predicate) -> Self {
+        match self {
+            Self::And(mut predicates) => {
+                predicates.push(other);
+                Self::And(predicates)
+            }
+            predicate => Self::And(vec![predicate, other]),
+        }
+    }
+
+    /// Объединение условий через `or`. Условие добавляется в конец цепочки `or`
+    pub fn or(self, other: Predicate) -> Self {
+        match self {
+            Self::Or(mut predicates) => {
+                predicates.push(other);
+                Self::Or(predicates)
+            }
+            predicate => Self::Or(vec![predicate, other]),
+        }
+    }
+}
+
+impl FromStr for Predicate {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = ExprParser {
+            tokens: tokenize(s)?,
+            position: 0,
+            depth: 0,
+        };
+        let predicate = parser.parse_or()?;
+        match parser.next() {
+            Some(token) => Err(token.unexpected()),
+            None => Ok(predicate),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    /// Имя поля, ключевое слово или значение без кавычек
+    Word,
+    /// Значение в кавычках
+    Str,
+    Operator(Operator),
+    LParen,
+    RParen,
+    Comma,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    /// Значение без кавычек или исходный текст лексемы
+    text: String,
+    position: usize,
+}
+
+impl Token {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(keyword)
+    }
+
+    fn unexpected(self) -> FilterError {
+        FilterError::UnexpectedToken {
+            position: self.position,
+            token: self.text,
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '+' | ':' | '.')
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, FilterError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((position, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, c)| c);
+        let operator = match (c, next) {
+            ('=', Some('=')) => Some((Operator::Eq, 2)),
+            ('=', _) => Some((Operator::Eq, 1)),
+            ('!', Some('=')) | ('<', Some('>')) => Some((Operator::Ne, 2)),
+            ('<', Some('=')) => Some((Operator::Le, 2)),
+            ('<', _) => Some((Operator::Lt, 1)),
+            ('>', Some('=')) => Some((Operator::Ge, 2)),
+            ('>', _) => Some((Operator::Gt, 1)),
+            _ => None,
+        };
+        if let Some((operator, len)) = operator {
+            if len == 2 {
+                chars.next();
+            }
+            tokens.push(Token {
+                kind: TokenKind::Operator(operator),
+                text: s[position..position + len].to_string(),
+                position,
+            });
+            continue;
+        }
+
+        let (kind, text) = match c {
+            c if c.is_whitespace() => continue,
+            '(' => (TokenKind::LParen, c.to_string()),
+            ')' => (TokenKind::RParen, c.to_string()),
+            ',' => (TokenKind::Comma, c.to_string()),
+            '\'' | '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        // Удвоенная кавычка внутри строки
+                        Some((_, q)) if q == c && chars.peek().map(|&(_, c)| c) == Some(c) => {
+                            chars.next();
+                            text.push(c);
+                        }
+                        Some((_, q)) if q == c => break,
+                        Some((_, q)) => text.push(q),
+                        None => return Err(FilterError::UnterminatedString { position }),
+                    }
+                }
+                (TokenKind::Str, text)
+            }
+            c if is_word_char(c) => {
+                let mut end = position + c.len_utf8();
+                while let Some(&(i, c)) = chars.peek()
+                    && is_word_char(c)
+                {
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                (TokenKind::Word, s[position..end].to_string())
+            }
+            c => return Err(FilterError::UnexpectedChar { position, char: c }),
+        };
+        tokens.push(Token {
+            kind,
+            text,
+            position,
+        });
+    }
+
+    Ok(tokens)
+}
+
+/// Максимальная глубина выражения. Глубину увеличивают только скобки и `not`,
+/// цепочки `and` и `or` хранятся списком, поэтому ограничена и рекурсия при проверке условия
+const MAX_DEPTH: usize = 128;
+
+/// Разбор выражения рекурсивным спуском.
+/// Приоритет операторов по возрастанию: `or`, `and`, `not`
+struct ExprParser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl ExprParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<Token, FilterError> {
+        match self.next() {
+            Some(token) if token.kind == kind => Ok(token),
+            Some(token) => Err(token.unexpected()),
+            None => Err(FilterError::UnexpectedEnd),
+        }
+    }
+
+    /// Пропуск ключевого слова, если оно следующее. Возвращает его позицию
+    fn next_keyword(&mut self, keyword: &str) -> Option<usize> {
+        let position = self
+            .peek()
+            .filter(|token| token.is_keyword(keyword))?
+            .position;
+        self.position += 1;
+        Some(position)
+    }
+
+    /// Увеличение глубины выражения с проверкой ограничения
+    fn enter(&mut self, position: usize) -> Result<(), FilterError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(FilterError::TooDeep {
+                position,
+                limit: MAX_DEPTH,
+            });
+        }
+        Ok(())
+    }
+
+    fn parse_or(&mut self) -> Result<Predicate, FilterError> {
+        let mut predicate = self.parse_and()?;
+        while self.next_keyword("or").is_some() {
+            predicate = predicate.or(self.parse_and()?);
+        }
+        Ok(predicate)
+    }
+
+    fn parse_and(&mut self) -> Result<Predicate, FilterError> {
+        let mut predicate = self.parse_not()?;
+        while self.next_keyword("and").is_some() {
+            predicate = predicate.and(self.parse_not()?);
+        }
+        Ok(predicate)
+    }
+
+    fn parse_not(&mut self) -> Result<Predicate, FilterError> {
+        if let Some(position) = self.next_keyword("not") {
+            self.enter(position)?;
+            let predicate = Predicate::Not(Box::new(self.parse_not()?));
+            self.depth -= 1;
+            return Ok(predicate);
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Predicate, FilterError> {
+        let token = self.next().ok_or(FilterError::UnexpectedEnd)?;
+        match token.kind {
+            TokenKind::LParen => {
+                self.enter(token.position)?;
+                let predicate = self.parse_or()?;
+                self.expect(TokenKind::RParen)?;
+                self.depth -= 1;
+                Ok(predicate)
+            }
+            TokenKind::Word => {
+                let field = token
+                    .text
+                    .parse::<Field>()
+                    .map_err(|_| FilterError::UnknownField {
+                        position: token.position,
+                        name: token.text,
+                    })?;
+                self.parse_condition(field)
+            }
+            _ => Err(token.unexpected()),
+        }
+    }
+
+    fn parse_condition(&mut self, field: Field) -> Result<Predicate, FilterError> {
+        let token = self.next().ok_or(FilterError::UnexpectedEnd)?;
+        match token.kind {
+            TokenKind::Operator(operator) => {
+                let value = parse_value(&field, self.next().ok_or(FilterError::UnexpectedEnd)?)?;
+                if operator != Operator::Eq
+                    && operator != Operator::Ne
+                    && matches!(value, Value::TxType(_) | Value::Status(_))
+                {
+                    return Err(FilterError::InvalidOperator {
+                        position: token.position,
+                        field,
+                        operator,
+                    });
+                }
+                Ok(Predicate::Compare {
+                    field,
+                    operator,
+                    value,
+                })
+            }
+            _ if token.is_keyword("in") => self.parse_in(field),
+            _ if token.is_keyword("not") => {
+                let token = self.next().ok_or(FilterError::UnexpectedEnd)?;
+                if !token.is_keyword("in") {
+                    return Err(token.unexpected());
+                }
+                Ok(Predicate::Not(Box::new(self.parse_in(field)?)))
+            }
+            _ => Err(token.unexpected()),
+        }
+    }
+
+    fn parse_in(&mut self, field: Field) -> Result<Predicate, FilterError> {
+        self.expect(TokenKind::LParen)?;
+        let mut values = Vec::new();
+        loop {
+            let token = self.next().ok_or(FilterError::UnexpectedEnd)?;
+            values.push(parse_value(&field, token)?);
+
+            let token = self.next().ok_or(FilterError::UnexpectedEnd)?;
+            match token.kind {
+                TokenKind::Comma => continue,
+                TokenKind::RParen => break,
+                _ => return Err(token.unexpected()),
+            }
+        }
+        Ok(Predicate::In { field, values })
+    }
+}
+
+/// Разбор значения в зависимости от поля
+fn parse_value(field: &Field, token: Token) -> Result<Value, FilterError> {
+    if !matches!(token.kind, TokenKind::Word | TokenKind::Str) {
+        return Err(token.unexpected());
+    }
+
+    let text = token.text.as_str();
+    let number = text.replace('_', "");
+    let value = match field {
+        Field::TxId | Field::FromUserId | Field::ToUserId | Field::Amount => {
+            number.parse::<u64>().ok().map(|n| Value::Number(n.into()))
+        }
+        Field::Timestamp => number
+            .parse::<i64>()
+            .ok()
+            .or_else(|| parse_date(text))
+            .map(|n| Value::Number(n.into())),
+        Field::TxType => text.to_ascii_uppercase().parse().ok().map(Value::TxType),
+        Field::Status => text.to_ascii_uppercase().parse().ok().map(Value::Status),
+        Field::Description => Some(Value::Text(token.text.clone())),
+    };
+
+    value.ok_or_else(|| FilterError::InvalidValue {
+        position: token.position,
+        field: field.clone(),
+        value: token.text,
+    })
+}
+
+/// Разбор даты `YYYY-MM-DD` или `YYYY-MM-DDTHH:MM[:SS]` в UTC в миллисекунды Unix-времени
+fn parse_date(text: &str) -> Option<i64> {
+    let text = text.strip_suffix('Z').unwrap_or(text);
+    let (date, time) = text.split_once('T').unwrap_or((text, "00:00"));
+
+    let mut date = date.splitn(3, '-');
+    let year: i64 = date.next()?.parse().ok()?;
+    let month: i64 = date.next()?.parse().ok()?;
+    let day: i64 = date.next()?.parse().ok()?;
+    // Год из четырёх цифр, иначе миллисекунды не помещаются в i64
+    if !(0..=9999).contains(&year)
+        || !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+    {
+        return None;
+    }
+
+    let mut time = time.splitn(3, ':');
+    let hours: i64 = time.next()?.parse().ok()?;
+    let minutes: i64 = time.next()?.parse().ok()?;
+    let seconds: i64 = time.next().map_or(Some(0), |s| s.parse().ok())?;
+    if hours > 23 || minutes > 59 || seconds > 59 {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    Some(((days * 24 + hours) * 60 + minutes) * 60_000 + seconds * 1000)
+}
+
+/// Количество дней в месяце с учётом високосных лет
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Количество дней от 1970-01-01 по григорианскому календарю
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_transactions() -> Vec<Transaction> {
+        vec![
+            Transaction {
+                tx_id: 1,
+                tx_type: TxType::Transfer,
+                from_user_id: 42,
+                amount: 20_000,
+                timestamp: 1_709_337_600_000,
+                description: "Перевод".to_string(),
+                ..Default::default()
+            },
+            Transaction {
+                tx_id: 2,
+                tx_type: TxType::Transfer,
+                from_user_id: 42,
+                amount: 5_000,
+                timestamp: 1_709_337_600_000,
+                ..Default::default()
+            },
+            Transaction {
+                tx_id: 3,
+                tx_type: TxType::Deposit,
+                amount: 30_000,
+                status: Status::Failure,
+                timestamp: 1_711_929_600_000,
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn filter(expr: &str) -> Vec<u64> {
+        let predicate: Predicate = expr.parse().unwrap();
+        get_transactions()
+            .iter()
+            .filter(|t| predicate.matches(t))
+            .map(|t| t.tx_id)
+            .collect()
+    }
+
+    #[test]
+    fn test_success_matches() {
+        let cases: [(&str, &[u64]); 12] = [
+            (
+                "STATUS = SUCCESS and TX_TYPE = TRANSFER and AMOUNT > 10_000 and FROM_USER_ID = 42",
+                &[1],
+            ),
+            ("amount >= 5000 and amount <= 20000", &[1, 2]),
+            ("AMOUNT < 10000 or status != success", &[2, 3]),
+            ("not (AMOUNT < 10000 or STATUS <> SUCCESS)", &[1]),
+            ("TX_ID in (1, 3, 5)", &[1, 3]),
+            ("TX_TYPE not in (DEPOSIT)", &[1, 2]),
+            ("DESCRIPTION = 'Перевод'", &[1]),
+            ("DESCRIPTION == \"\"", &[2, 3]),
+            (
+                "TIMESTAMP >= 2024-03-01 and TIMESTAMP < 2024-04-01T00:00:00Z",
+                &[1, 2],
+            ),
+            ("TX_ID = 1 or TX_ID = 2 and AMOUNT > 10000", &[1]),
+            ("(TX_ID = 1 or TX_ID = 2) and AMOUNT < 10000", &[2]),
+            ("TO_USER_ID = 0 and not not TX_ID = 3", &[3]),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(filter(expr), expected, "{expr}");
+        }
+    }
+
+    #[test]
+    fn test_success_parse_date() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2021-09-30T21:21"), Some(1_633_036_860_000));
+        assert_eq!(parse_date("2024-02-29T23:59:59"), Some(1_709_251_199_000));
+        assert_eq!(parse_date("2000-02-29"), Some(951_782_400_000));
+        assert_eq!(parse_date("2024-13-01"), None);
+    }
+
+    #[test]
+    fn test_error_parse() {
+        let cases = [
+            ("AMOUNT ! 1", "Неожиданный символ '!' в позиции 7"),
+            ("DESCRIPTION = 'a", "Незакрытая кавычка в позиции 14"),
+            ("AMOUNT = 1 TX_ID", "Неожиданное \"TX_ID\" в позиции 11"),
+            ("AMOUNT =", "Неожиданный конец выражения"),
+            ("(AMOUNT = 1", "Неожиданный конец выражения"),
+            ("TX_ID in (1 2)", "Неожиданное \"2\" в позиции 12"),
+            ("USER = 1", "Неизвестное поле USER в позиции 0"),
+            (
+                "STATUS = DONE",
+                "Некорректное значение \"DONE\" для поля STATUS в позиции 9",
+            ),
+            (
+                "AMOUNT > -1",
+                "Некорректное значение \"-1\" для поля AMOUNT в позиции 9",
+            ),
+            (
+                "TIMESTAMP > 2024-02-31",
+                "Некорректное значение \"2024-02-31\" для поля TIMESTAMP в позиции 12",
+            ),
+            (
+                "TIMESTAMP > 2023-04-31",
+                "Некорректное значение \"2023-04-31\" для поля TIMESTAMP в позиции 12",
+            ),
+            (
+                "TIMESTAMP > 2023-02-29",
+                "Некорректное значение \"2023-02-29\" для поля TIMESTAMP в позиции 12",
+            ),
+            (
+                "TIMESTAMP > 1900-02-29",
+                "Некорректное значение \"1900-02-29\" для поля TIMESTAMP в позиции 12",
+            ),
+            (
+                "TIMESTAMP > 9999999999999-01-01",
+                "Некорректное значение \"9999999999999-01-01\" для поля TIMESTAMP в позиции 12",
+            ),
+            (
+                "STATUS > SUCCESS",
+                "Оператор > неприменим к полю STATUS в позиции 7",
+            ),
+        ];
+        for (expr, expected) in cases {
+            let result = expr.parse::<Predicate>().unwrap_err();
+            assert_eq!(result.to_string(), expected, "{expr}");
+        }
+    }
+
+    #[test]
+    fn test_error_too_deep() {
+        let nested = |depth: usize| format!("{}TX_ID = 1{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(nested(MAX_DEPTH).parse::<Predicate>().is_ok());
+        assert_eq!(
+            nested(100_000).parse::<Predicate>().unwrap_err(),
+            FilterError::TooDeep {
+                position: MAX_DEPTH,
+                limit: MAX_DEPTH,
+            }
+        );
+
+        let negated = format!("{}TX_ID = 1", "not ".repeat(100_000));
+        assert_eq!(
+            negated.parse::<Predicate>().unwrap_err().to_string(),
+            "Превышена глубина выражения 128 в позиции 512"
+        );
+
+        // цепочки and и or не увеличивают глубину
+        let chain = vec!["TX_ID = 1"; 100_000].join(" or ");
+        let Predicate::Or(predicates) = chain.parse::<Predicate>().unwrap() else {
+            panic!("ожидалась цепочка or");
+        };
+        assert_eq!(predicates.len(), 100_000);
+        let chain = vec![nested(MAX_DEPTH); 100].join(" and ");
+        assert!(chain.parse::<Predicate>().is_ok());
+    }
+}
@@ -7,17 +7,52 @@ pub mod parsers;
 /// Ошибки
 pub mod errors;
 
-use std::io::{Read, Write};
+/// Построитель транзакций
+pub mod builder;
+
+/// Сжатие файлов
+pub mod compression;
+
+/// Реестр форматов
+pub mod registry;
+
+/// Сравнение наборов транзакций
+pub mod diff;
+
+/// Атомарная запись файлов
+pub mod atomic;
+
+/// Фильтрация транзакций
+pub mod filter;
+
+/// Сортировка, удаление повторов и ограничение количества транзакций
+pub mod transform;
+
+/// Объединение наборов транзакций из нескольких источников
+pub mod merge;
+
+use std::{
+    fmt,
+    io::{BufRead, BufReader, Read, Write},
+};
 
 use strum::{Display, EnumString};
 
 use crate::{
+    builder::TransactionBuilder,
     errors::{ParserError, WriteError},
-    parsers::{bin::parser::BinParser, csv::parser::CsvParser, txt::parser::TxtParser},
+    parsers::{
+        bin::{self, parser::BinParser},
+        csv::{self, parser::CsvParser},
+        json::{self, parser::JsonParser},
+        jsonl::{self, parser::JsonlParser},
+        txt::{self, parser::TxtParser},
+    },
+    registry::{FormatEntry, FormatRegistry},
 };
 
 /// Виды парсеров
-#[derive(Debug, Display, EnumString)]
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString)]
 pub enum ParserType {
     /// csv
     #[strum(serialize = "csv")]
@@ -28,20 +63,60 @@ pub enum ParserType {
     /// bin
     #[strum(serialize = "bin")]
     Bin,
+    /// json, массив объектов
+    #[strum(serialize = "json")]
+    Json,
+    /// jsonl, один объект на строку
+    #[strum(serialize = "jsonl")]
+    Jsonl,
 }
 
 impl ParserType {
-    fn get_ext(value: &str) -> Result<Self, ParserError> {
-        let ext = value.split(".").last().ok_or(ParserError::UnknownExt)?;
-        let parser_type = ext
-            .parse::<ParserType>()
-            .map_err(|_| ParserError::UnknownExt)?;
-        Ok(parser_type)
+    /// Встроенные форматы в порядке проверки содержимого
+    pub fn all() -> [Self; 5] {
+        [Self::Bin, Self::Csv, Self::Txt, Self::Json, Self::Jsonl]
+    }
+
+    /// Описание встроенного формата для [`FormatRegistry`]
+    pub fn format(self) -> FormatEntry {
+        let name = self.to_string();
+        let format = match self {
+            Self::Csv => FormatEntry::new(&name, CsvParser::new()).sniffer(csv::parser::sniff),
+            Self::Txt => FormatEntry::new(&name, TxtParser::new()).sniffer(txt::parser::sniff),
+            Self::Bin => FormatEntry::new(&name, BinParser::new()).sniffer(bin::parser::sniff),
+            Self::Json => FormatEntry::new(&name, JsonParser::new()).sniffer(json::parser::sniff),
+            Self::Jsonl => {
+                FormatEntry::new(&name, JsonlParser::new()).sniffer(jsonl::parser::sniff)
+            }
+        };
+        format.extension(name)
+    }
+
+    /// Определение формата по началу содержимого файла.
+    /// Если содержимое подходит под несколько форматов, возвращается [`ParserError::AmbiguousFormat`]
+    pub fn detect(data: &[u8]) -> Result<Self, ParserError> {
+        detect_single(Self::all(), |parser_type| {
+            parser_type.format().matches(data)
+        })
+    }
+}
+
+/// Единственный формат, под который подходит содержимое файла
+pub(crate) fn detect_single<T>(
+    formats: impl IntoIterator<Item = T>,
+    matches: impl Fn(&T) -> bool,
+) -> Result<T, ParserError> {
+    let mut found = formats.into_iter().filter(|format| matches(format));
+    match (found.next(), found.next()) {
+        (Some(format), None) => Ok(format),
+        (Some(_), Some(_)) => Err(ParserError::AmbiguousFormat),
+        (None, _) => Err(ParserError::UnknownContent),
     }
 }
 
 /// Поля транзакции
-#[derive(Debug, Clone, PartialEq, Display)]
+#[derive(Debug, Clone, PartialEq, Display, EnumString)]
+#[strum(ascii_case_insensitive)]
 pub enum Field {
     /// Уникальный идентификатор транзакции
     #[strum(serialize = "TX_ID")]
@@ -85,7 +160,7 @@ impl Field {
 }
 
 /// Тип транзакции
-#[derive(Debug, Default, PartialEq, Display, EnumString)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Display, EnumString)]
 pub enum TxType {
     /// Поступление
     #[default]
@@ -100,7 +175,7 @@ pub enum TxType {
 }
 
 /// Статус транзакции
-#[derive(Debug, Default, PartialEq, Display, EnumString)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Display, EnumString)]
 pub enum Status {
     /// Успешная
     #[default]
@@ -115,7 +190,7 @@ pub enum Status {
 }
 
 /// Транзакция
-#[derive(Debug, Default, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct Transaction {
     /// Уникальный идентификатор транзакции
     tx_id: u64,
@@ -136,6 +211,91 @@ pub struct Transaction {
 }
 
 impl Transaction {
+    /// Создание построителя транзакции
+    pub fn builder() -> TransactionBuilder {
+        TransactionBuilder::default()
+    }
+
+    /// Уникальный идентификатор транзакции
+    pub fn tx_id(&self) -> u64 {
+        self.tx_id
+    }
+
+    /// Тип транзакции
+    pub fn tx_type(&self) -> TxType {
+        self.tx_type
+    }
+
+    /// Идентификатор пользователя-отправителя
+    pub fn from_user_id(&self) -> u64 {
+        self.from_user_id
+    }
+
+    /// Идентификатор пользователя-получателя
+    pub fn to_user_id(&self) -> u64 {
+        self.to_user_id
+    }
+
+    /// Сумма транзакции в наименьших единицах валюты
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    /// Время совершения транзакции в формате Unix-времени
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    /// Статус транзакции
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// Текстовое описание транзакции
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Изменение уникального идентификатора транзакции
+    pub fn set_tx_id(&mut self, value: u64) {
+        self.tx_id = value;
+    }
+
+    /// Изменение типа транзакции
+    pub fn set_tx_type(&mut self, value: TxType) {
+        self.tx_type = value;
+    }
+
+    /// Изменение идентификатора пользователя-отправителя
+    pub fn set_from_user_id(&mut self, value: u64) {
+        self.from_user_id = value;
+    }
+
+    /// Изменение идентификатора пользователя-получателя
+    pub fn set_to_user_id(&mut self, value: u64) {
+        self.to_user_id = value;
+    }
+
+    /// Изменение суммы транзакции
+    pub fn set_amount(&mut self, value: u64) {
+        self.amount = value;
+    }
+
+    /// Изменение времени совершения транзакции
+    pub fn set_timestamp(&mut self, value: i64) {
+        self.timestamp = value;
+    }
+
+    /// Изменение статуса транзакции
+    pub fn set_status(&mut self, value: Status) {
+        self.status = value;
+    }
+
+    /// Изменение текстового описания транзакции
+    pub fn set_description(&mut self, value: impl Into<String>) {
+        self.description = value.into();
+    }
+
     fn get_value(&self, field: &Field) -> String {
         match field {
             Field::TxId => self.tx_id.to_string(),
@@ -150,44 +310,495 @@ impl Transaction {
     }
 }
 
+/// Транзакция, заимствующая описание из исходных данных без копирования
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransactionRef<'a> {
+    tx_id: u64,
+    tx_type: TxType,
+    from_user_id: u64,
+    to_user_id: u64,
+    amount: u64,
+    timestamp: i64,
+    status: Status,
+    description: &'a str,
+}
+
+impl<'a> TransactionRef<'a> {
+    /// Уникальный идентификатор транзакции
+    pub fn tx_id(&self) -> u64 {
+        self.tx_id
+    }
+
+    /// Тип транзакции
+    pub fn tx_type(&self) -> TxType {
+        self.tx_type
+    }
+
+    /// Идентификатор пользователя-отправителя
+    pub fn from_user_id(&self) -> u64 {
+        self.from_user_id
+    }
+
+    /// Идентификатор пользователя-получателя
+    pub fn to_user_id(&self) -> u64 {
+        self.to_user_id
+    }
+
+    /// Сумма транзакции в наименьших единицах валюты
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    /// Время совершения транзакции в формате Unix-времени
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    /// Статус транзакции
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// Текстовое описание транзакции
+    pub fn description(&self) -> &'a str {
+        self.description
+    }
+
+    /// Создание транзакции с копией описания
+    pub fn to_transaction(&self) -> Transaction {
+        Transaction::from(*self)
+    }
+}
+
+impl From<TransactionRef<'_>> for Transaction {
+    fn from(value: TransactionRef<'_>) -> Self {
+        Self {
+            tx_id: value.tx_id,
+            tx_type: value.tx_type,
+            from_user_id: value.from_user_id,
+            to_user_id: value.to_user_id,
+            amount: value.amount,
+            timestamp: value.timestamp,
+            status: value.status,
+            description: value.description.to_string(),
+        }
+    }
+}
+
+/// Режим разбора
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ParseMode {
+    /// Остановка на первой ошибке
+    #[default]
+    Strict,
+    /// Пропуск некорректных записей с сохранением всех ошибок
+    Lenient,
+}
+
+/// Ограничения на размер входных данных.
+/// По умолчанию ограничения не заданы
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ParseLimits {
+    /// Максимальная длина описания в байтах
+    pub max_description_bytes: Option<usize>,
+    /// Максимальное количество записей
+    pub max_records: Option<usize>,
+    /// Максимальная длина строки в байтах без символов перевода строки.
+    /// Для csv ограничивает всю запись, включая переводы строк внутри кавычек
+    pub max_line_length: Option<usize>,
+    /// Максимальный размер входных данных в байтах
+    pub max_input_bytes: Option<u64>,
+}
+
+/// Настройки разбора
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ParseOptions {
+    /// Режим разбора
+    pub mode: ParseMode,
+    /// Ограничения на размер входных данных
+    pub limits: ParseLimits,
+}
+
+/// Ошибка в записи, пропущенной при разборе
+#[derive(Debug)]
+pub struct Diagnostic {
+    /// Порядковый номер записи в файле
+    pub index: usize,
+    /// Ошибка разбора записи
+    pub error: ParserError,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Запись {}: {}", self.index, self.error)
+    }
+}
+
 /// Парсер
 pub trait Parser {
     /// Ошибка чтения
     type Error;
 
+    /// Потоковое чтение транзакций по одной записи с заданными настройками разбора
+    fn records_with_options<R: BufRead>(
+        reader: R,
+        options: ParseOptions,
+    ) -> impl Iterator<Item = Result<Transaction, Self::Error>>;
+
+    /// Потоковое чтение транзакций по одной записи в заданном режиме разбора
+    fn records_with_mode<R: BufRead>(
+        reader: R,
+        mode: ParseMode,
+    ) -> impl Iterator<Item = Result<Transaction, Self::Error>> {
+        Self::records_with_options(
+            reader,
+            ParseOptions {
+                mode,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Потоковое чтение транзакций по одной записи
+    fn records<R: BufRead>(reader: R) -> impl Iterator<Item = Result<Transaction, Self::Error>> {
+        Self::records_with_mode(reader, ParseMode::Strict)
+    }
+
     /// Чтение транзаций из файла
-    fn from_read<R: Read>(reader: &mut R) -> Result<Vec<Transaction>, Self::Error>;
+    fn from_read<R: Read>(reader: &mut R) -> Result<Vec<Transaction>, Self::Error> {
+        Self::records(BufReader::new(reader)).collect()
+    }
+
+    /// Создание потокового писателя транзакций
+    fn writer<W: Write>(writer: W) -> impl TransactionWriter;
 
     /// Запись транзаций в файл
-    fn write_to<W: Write>(writer: &mut W, transactions: &[Transaction]) -> Result<(), WriteError>;
+    fn write_to<W: Write>(writer: &mut W, transactions: &[Transaction]) -> Result<(), WriteError> {
+        let mut writer = Self::writer(writer);
+        for t in transactions {
+            writer.write(t)?;
+        }
+        writer.finish()
+    }
 }
 
-/// Чтение транзаций из файла
-pub fn from_read<R: Read>(reader: &mut R, from: &str) -> Result<Vec<Transaction>, ParserError> {
-    let from_ext = ParserType::get_ext(from)?;
+/// Потоковая запись транзакций по одной записи
+pub trait TransactionWriter {
+    /// Запись одной транзакции
+    fn write(&mut self, transaction: &Transaction) -> Result<(), WriteError>;
+
+    /// Завершение записи
+    fn finish(&mut self) -> Result<(), WriteError>;
+}
+
+/// Формат с настройками, заданными для экземпляра.
+/// В отличие от [`Parser`] может использоваться как `Box<dyn Format>`
+pub trait Format: Send + Sync {
+    /// Настройки разбора
+    fn parse_options(&self) -> ParseOptions;
+
+    /// Копия формата с другими настройками разбора
+    fn with_parse_options(&self, options: ParseOptions) -> Box<dyn Format>;
 
-    let transactions = match from_ext {
-        ParserType::Csv => CsvParser::from_read(reader)?,
-        ParserType::Txt => TxtParser::from_read(reader)?,
-        ParserType::Bin => BinParser::from_read(reader)?,
-    };
+    /// Потоковое чтение транзакций по одной записи
+    fn read_records<'a>(
+        &self,
+        reader: &'a mut dyn BufRead,
+    ) -> Box<dyn Iterator<Item = Result<Transaction, ParserError>> + 'a>;
 
-    Ok(transactions)
+    /// Создание потокового писателя транзакций
+    fn create_writer<'a>(&self, writer: &'a mut dyn Write) -> Box<dyn TransactionWriter + 'a>;
+
+    /// Чтение транзаций.
+    /// В режиме [`ParseMode::Lenient`] некорректные записи пропускаются,
+    /// а ошибки возвращаются вместе с прочитанными транзакциями
+    fn read_transactions(
+        &self,
+        reader: &mut dyn BufRead,
+    ) -> Result<(Vec<Transaction>, Vec<Diagnostic>), ParserError> {
+        let lenient = self.parse_options().mode == ParseMode::Lenient;
+        let mut transactions = Vec::new();
+        let mut diagnostics = Vec::new();
+
+        for (index, record) in self.read_records(reader).enumerate() {
+            match record {
+                Ok(transaction) => transactions.push(transaction),
+                Err(error) if lenient => diagnostics.push(Diagnostic { index, error }),
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok((transactions, diagnostics))
+    }
+
+    /// Запись транзаций
+    fn write_transactions(
+        &self,
+        writer: &mut dyn Write,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        let mut writer = self.create_writer(writer);
+        for t in transactions {
+            writer.write(t)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
 }
 
-/// Запись транзаций в файл
+/// Чтение транзаций из файла.
+/// Файлы со сжатием, например `.csv.gz`, распаковываются при чтении
+pub fn from_read<R: Read>(reader: &mut R, from: &str) -> Result<Vec<Transaction>, ParserError> {
+    FormatRegistry::default().from_read(reader, from)
+}
+
+/// Запись транзаций в файл.
+/// Для файлов со сжатием, например `.csv.gz`, данные сжимаются при записи
 pub fn write_to<W: Write>(
     writer: &mut W,
     transactions: &[Transaction],
     to: &str,
 ) -> Result<(), ParserError> {
-    let to_ext = ParserType::get_ext(to)?;
+    FormatRegistry::default().write_to(writer, transactions, to)
+}
+
+/// Чтение транзаций с определением сжатия и формата по содержимому
+pub fn from_read_auto<R: Read>(reader: &mut R) -> Result<Vec<Transaction>, ParserError> {
+    FormatRegistry::default().from_read_auto(reader)
+}
+
+/// Чтение транзаций из файла в заданном режиме разбора.
+/// В режиме [`ParseMode::Lenient`] некорректные записи пропускаются,
+/// а ошибки возвращаются вместе с прочитанными транзакциями
+pub fn from_read_with_mode<R: Read>(
+    reader: &mut R,
+    from: &str,
+    mode: ParseMode,
+) -> Result<(Vec<Transaction>, Vec<Diagnostic>), ParserError> {
+    from_read_with_options(
+        reader,
+        from,
+        ParseOptions {
+            mode,
+            ..Default::default()
+        },
+    )
+}
+
+/// Чтение транзаций из файла с заданными настройками разбора
+pub fn from_read_with_options<R: Read>(
+    reader: &mut R,
+    from: &str,
+    options: ParseOptions,
+) -> Result<(Vec<Transaction>, Vec<Diagnostic>), ParserError> {
+    FormatRegistry::default().from_read_with_options(reader, from, options)
+}
+
+/// Потоковая конвертация транзакций из одного формата в другой.
+/// Если расширение входного файла неизвестно, сжатие и формат определяются по содержимому.
+/// Возвращает количество записанных транзакций
+pub fn convert<R: Read, W: Write>(
+    reader: &mut R,
+    from: &str,
+    writer: &mut W,
+    to: &str,
+) -> Result<usize, ParserError> {
+    FormatRegistry::default().convert(reader, from, writer, to)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_success_convert() {
+        let transactions: Vec<Transaction> = (0..3)
+            .map(|tx_id| Transaction {
+                tx_id,
+                description: format!("record {tx_id}"),
+                ..Default::default()
+            })
+            .collect();
 
-    match to_ext {
-        ParserType::Csv => CsvParser::write_to(writer, transactions)?,
-        ParserType::Txt => TxtParser::write_to(writer, transactions)?,
-        ParserType::Bin => BinParser::write_to(writer, transactions)?,
+        let mut csv = Vec::new();
+        write_to(&mut csv, &transactions, "a.csv").unwrap();
+
+        let mut bin = Vec::new();
+        let count = convert(&mut Cursor::new(csv), "a.csv", &mut bin, "b.bin").unwrap();
+        assert_eq!(count, 3);
+
+        let mut txt = Vec::new();
+        convert(&mut Cursor::new(bin), "b.bin", &mut txt, "c.txt").unwrap();
+
+        let result = from_read(&mut Cursor::new(txt), "c.txt").unwrap();
+        assert_eq!(result, transactions);
     }
 
-    Ok(())
+    #[cfg(all(feature = "gzip", feature = "zstd"))]
+    #[test]
+    fn test_success_compression() {
+        use crate::compression::Compression;
+
+        let transactions: Vec<Transaction> = (0..3)
+            .map(|tx_id| Transaction {
+                tx_id,
+                description: format!("record {tx_id}"),
+                ..Default::default()
+            })
+            .collect();
+
+        let mut csv = Vec::new();
+        write_to(&mut csv, &transactions, "a.csv.gz").unwrap();
+        assert_eq!(Compression::detect(&csv), Compression::Gzip);
+        assert_eq!(
+            from_read(&mut Cursor::new(&csv), "a.csv.gz").unwrap(),
+            transactions
+        );
+
+        let mut txt = Vec::new();
+        convert(&mut Cursor::new(&csv), "a.csv.gz", &mut txt, "b.txt.zst").unwrap();
+        assert_eq!(Compression::detect(&txt), Compression::Zstd);
+
+        let mut bin = Vec::new();
+        convert(&mut Cursor::new(&txt), "b.data", &mut bin, "c.bin.gz").unwrap();
+        assert_eq!(
+            from_read_auto(&mut Cursor::new(&bin)).unwrap(),
+            transactions
+        );
+
+        assert!(from_read(&mut Cursor::new(&csv), "a.csv.zst").is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        let transactions: Vec<Transaction> = (1..=2)
+            .map(|tx_id| Transaction {
+                tx_id,
+                description: format!("record; {tx_id}"),
+                ..Default::default()
+            })
+            .collect();
+        let formats: Vec<Box<dyn Format>> = vec![
+            Box::new(CsvParser::new().with_delimiter(';')),
+            Box::new(TxtParser::new()),
+            Box::new(BinParser::new().with_version(bin::header::BinVersion::V1)),
+            Box::new(JsonParser::new()),
+            Box::new(JsonlParser::new()),
+        ];
+        for format in &formats {
+            let mut data = Vec::new();
+            format.write_transactions(&mut data, &transactions).unwrap();
+            let (result, _) = format.read_transactions(&mut data.as_slice()).unwrap();
+            assert_eq!(result, transactions);
+        }
+
+        let mut data = Vec::new();
+        formats[0]
+            .write_transactions(&mut data, &transactions[..1])
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(data).unwrap(),
+            "TX_ID;TX_TYPE;FROM_USER_ID;TO_USER_ID;AMOUNT;TIMESTAMP;STATUS;DESCRIPTION\n\
+             1;DEPOSIT;0;0;0;0;SUCCESS;\"record; 1\"\n"
+        );
+
+        let data = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n\
+                    1,DEPOSIT,0,0,0,0,SUCCESS,\"a\"\n\
+                    2,DEPOSIT,0,0,x,0,SUCCESS,\"b\"\n";
+        let strict: Box<dyn Format> = Box::new(CsvParser::new());
+        assert!(strict.read_transactions(&mut data.as_bytes()).is_err());
+        let lenient = strict.with_parse_options(ParseOptions {
+            mode: ParseMode::Lenient,
+            ..Default::default()
+        });
+        let (result, diagnostics) = lenient.read_transactions(&mut data.as_bytes()).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["Запись 1: Ошибка csv парсера: Ошибка парсинга поля AMOUNT в строке 2, столбце 14"]
+        );
+    }
+
+    #[test]
+    fn test_from_read_with_mode() {
+        let header = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION";
+        let data = format!(
+            "{header}\n1,DEPOSIT,0,1,100,0,SUCCESS,\"a\"\n2,DEPOSIT,0,1,!,0,SUCCESS,\"b\"\n3,DEPOSIT,0,1,100,0,SUCCESS,\"c\"\n4,!,0,1,100,0,SUCCESS,\"d\"\n"
+        );
+
+        let result = from_read_with_mode(&mut data.as_bytes(), "a.csv", ParseMode::Strict);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Ошибка csv парсера: Ошибка парсинга поля AMOUNT в строке 2, столбце 14"
+        );
+
+        let (transactions, diagnostics) =
+            from_read_with_mode(&mut data.as_bytes(), "a.csv", ParseMode::Lenient).unwrap();
+        assert_eq!(
+            transactions.iter().map(|t| t.tx_id).collect::<Vec<_>>(),
+            [1, 3]
+        );
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>(),
+            [
+                "Запись 1: Ошибка csv парсера: Ошибка парсинга поля AMOUNT в строке 2, столбце 14",
+                "Запись 3: Ошибка csv парсера: Ошибка парсинга поля TX_TYPE в строке 4, столбце 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_detect() {
+        let transactions = [Transaction::default()];
+        for parser_type in [
+            ParserType::Csv,
+            ParserType::Txt,
+            ParserType::Bin,
+            ParserType::Json,
+            ParserType::Jsonl,
+        ] {
+            let mut data = Vec::new();
+            write_to(&mut data, &transactions, &format!("a.{parser_type}")).unwrap();
+            assert_eq!(ParserType::detect(&data).unwrap(), parser_type);
+            assert_eq!(
+                from_read_auto(&mut Cursor::new(data)).unwrap(),
+                transactions
+            );
+        }
+
+        let data = "\u{feff}# comment\n\nTX_ID: 1\n";
+        assert_eq!(
+            ParserType::detect(data.as_bytes()).unwrap(),
+            ParserType::Txt
+        );
+
+        for data in ["", "hello", "TX_ID;TX_TYPE\n"] {
+            let result = ParserType::detect(data.as_bytes()).unwrap_err();
+            assert_eq!(
+                result.to_string(),
+                "Не удалось определить формат файла по содержимому"
+            );
+        }
+    }
+
+    #[test]
+    fn test_error_detect_single() {
+        assert_eq!(detect_single([1, 2, 3], |n| *n > 2).unwrap(), 3);
+        assert!(matches!(
+            detect_single([1, 2, 3], |n| *n > 1),
+            Err(ParserError::AmbiguousFormat)
+        ));
+        assert!(matches!(
+            detect_single([1, 2, 3], |n| *n > 3),
+            Err(ParserError::UnknownContent)
+        ));
+    }
 }
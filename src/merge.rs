@@ -0,0 +1,245 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use strum::{Display, EnumString};
+
+use crate::{Transaction, errors::MergeError};
+
+/// Порядок транзакций при объединении
+#[derive(Debug, Default, Clone, Copy, PartialEq, Display, EnumString)]
+#[strum(ascii_case_insensitive)]
+pub enum MergeMode {
+    /// Источники следуют друг за другом
+    #[default]
+    #[strum(serialize = "concat")]
+    Concat,
+    /// Транзакции упорядочены по `TIMESTAMP`, при равенстве сохраняется порядок источников
+    #[strum(serialize = "timestamp")]
+    Timestamp,
+}
+
+/// Потоковое объединение источников транзакций.
+/// Все транзакции передаются дальше, в том числе повторы (для их удаления есть
+/// [`crate::transform::Transforms`]), а транзакции с одним `TX_ID` и разным содержимым
+/// приводят к ошибке [`MergeError::Conflict`] с номерами источников.
+/// Для проверки хранится первая транзакция с каждым идентификатором.
+///
+/// В режиме [`MergeMode::Timestamp`] источники сливаются без чтения целиком,
+/// поэтому каждый из них должен быть упорядочен по `TIMESTAMP`, иначе возвращается
+/// ошибка [`MergeError::Unsorted`]. Ошибки источников передаются дальше без изменений
+pub fn merge<I, E>(sources: impl IntoIterator<Item = I>, mode: MergeMode) -> Merge<I>
+where
+    I: Iterator<Item = Result<Transaction, E>>,
+    E: From<MergeError>,
+{
+    let sources: Vec<I> = sources.into_iter().collect();
+    Merge {
+        mode,
+        heads: sources.iter().map(|_| None).collect(),
+        last: vec![None; sources.len()],
+        sources,
+        current: 0,
+        queue: BinaryHeap::new(),
+        pulled: 0,
+        refill: None,
+        seen: HashMap::new(),
+    }
+}
+
+/// Итератор объединённых транзакций, создаётся в [`merge`]
+pub struct Merge<I> {
+    mode: MergeMode,
+    sources: Vec<I>,
+    /// Источник, из которого читаются транзакции в режиме [`MergeMode::Concat`]
+    current: usize,
+    /// Очередная транзакция каждого источника в режиме [`MergeMode::Timestamp`]
+    heads: Vec<Option<Transaction>>,
+    /// `TIMESTAMP` предыдущей транзакции каждого источника
+    last: Vec<Option<i64>>,
+    /// Источники с очередной транзакцией, упорядоченные по `TIMESTAMP` и номеру источника
+    queue: BinaryHeap<Reverse<(i64, usize)>>,
+    /// Количество источников, первая транзакция которых уже прочитана в очередь
+    pulled: usize,
+    /// Источник выданной транзакции, следующая транзакция которого ещё не прочитана
+    refill: Option<usize>,
+    /// Первая транзакция с каждым идентификатором и её источник
+    seen: HashMap<u64, (usize, Transaction)>,
+}
+
+impl<I, E> Merge<I>
+where
+    I: Iterator<Item = Result<Transaction, E>>,
+    E: From<MergeError>,
+{
+    /// Чтение следующей транзакции источника в очередь
+    fn pull(&mut self, source: usize) -> Result<(), E> {
+        let Some(t) = self.sources[source].next().transpose()? else {
+            return Ok(());
+        };
+        if self.last[source].is_some_and(|last| t.timestamp < last) {
+            return Err(MergeError::Unsorted {
+                tx_id: t.tx_id,
+                source_index: source,
+            }
+            .into());
+        }
+        self.last[source] = Some(t.timestamp);
+        self.queue.push(Reverse((t.timestamp, source)));
+        self.heads[source] = Some(t);
+        Ok(())
+    }
+
+    /// Следующая транзакция и номер её источника
+    fn next_any(&mut self) -> Option<Result<(usize, Transaction), E>> {
+        match self.mode {
+            MergeMode::Concat => {
+                while let Some(source) = self.sources.get_mut(self.current) {
+                    match source.next() {
+                        Some(record) => return Some(record.map(|t| (self.current, t))),
+                        None => self.current += 1,
+                    }
+                }
+                None
+            }
+            MergeMode::Timestamp => {
+                while self.pulled < self.sources.len() {
+                    self.pulled += 1;
+                    if let Err(e) = self.pull(self.pulled - 1) {
+                        return Some(Err(e));
+                    }
+                }
+                if let Some(source) = self.refill.take()
+                    && let Err(e) = self.pull(source)
+                {
+                    return Some(Err(e));
+                }
+                let Reverse((_, source)) = self.queue.pop()?;
+                self.refill = Some(source);
+                self.heads[source].take().map(|t| Ok((source, t)))
+            }
+        }
+    }
+}
+
+impl<I, E> Iterator for Merge<I>
+where
+    I: Iterator<Item = Result<Transaction, E>>,
+    E: From<MergeError>,
+{
+    type Item = Result<Transaction, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (source, t) = match self.next_any()? {
+            Ok(next) => next,
+            Err(e) => return Some(Err(e)),
+        };
+
+        match self.seen.get(&t.tx_id) {
+            Some((first, seen)) if *seen != t => Some(Err(MergeError::Conflict {
+                tx_id: t.tx_id,
+                first: *first,
+                second: source,
+            }
+            .into())),
+            Some(_) => Some(Ok(t)),
+            None => {
+                self.seen.insert(t.tx_id, (source, t.clone()));
+                Some(Ok(t))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_transaction(tx_id: u64, timestamp: i64) -> Transaction {
+        Transaction {
+            tx_id,
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    fn get_sources() -> Vec<Vec<Transaction>> {
+        vec![
+            vec![get_transaction(1, 10), get_transaction(3, 30)],
+            vec![get_transaction(2, 10), get_transaction(1, 10)],
+            vec![get_transaction(4, 5)],
+        ]
+    }
+
+    fn run(
+        sources: Vec<Vec<Transaction>>,
+        mode: MergeMode,
+    ) -> Result<Vec<Transaction>, MergeError> {
+        merge(
+            sources
+                .into_iter()
+                .map(|source| source.into_iter().map(Ok::<_, MergeError>)),
+            mode,
+        )
+        .collect()
+    }
+
+    #[test]
+    fn test_success_merge() {
+        let ids = |transactions: Vec<Transaction>| -> Vec<u64> {
+            transactions.iter().map(|t| t.tx_id).collect()
+        };
+
+        let result = run(get_sources(), MergeMode::Concat).unwrap();
+        assert_eq!(ids(result), [1, 3, 2, 1, 4]);
+
+        let result = run(get_sources(), MergeMode::Timestamp).unwrap();
+        assert_eq!(ids(result), [4, 1, 2, 1, 3]);
+    }
+
+    #[test]
+    fn test_error_conflict() {
+        let mut sources = get_sources();
+        sources[2].push(get_transaction(3, 31));
+
+        let result = run(sources, MergeMode::Concat).unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Транзакция 3 отличается в источниках 0 и 2"
+        );
+    }
+
+    #[test]
+    fn test_error_unsorted() {
+        let mut sources = get_sources();
+        sources[1].push(get_transaction(5, 0));
+
+        assert_eq!(run(sources.clone(), MergeMode::Concat).unwrap().len(), 6);
+        let result = run(sources, MergeMode::Timestamp).unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Транзакция 5 в источнике 1 нарушает порядок по TIMESTAMP"
+        );
+    }
+
+    #[test]
+    fn test_error_source() {
+        let unsorted = || MergeError::Unsorted {
+            tx_id: 0,
+            source_index: 7,
+        };
+        let cases = [
+            (MergeMode::Concat, [Ok(1), Err(unsorted())]),
+            // первые транзакции всех источников читаются до начала слияния
+            (MergeMode::Timestamp, [Err(unsorted()), Ok(1)]),
+        ];
+        for (mode, expected) in cases {
+            let sources = vec![vec![Ok(get_transaction(1, 10))], vec![Err(unsorted())]];
+            let result: Vec<_> = merge(sources.into_iter().map(Vec::into_iter), mode)
+                .map(|r| r.map(|t| t.tx_id))
+                .collect();
+            assert_eq!(result, expected);
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use crate::{
+    Field, Status, TransactionRef, TxType,
+    parsers::{
+        bin::{
+            error::BinError,
+            header::{self, BinHeader, BinVersion, FILE_MAGIC},
+            parser::{MAGIC, RECORD_SIZE_WITHOUT_DESC},
+        },
+        utils::description_trim,
+    },
+};
+
+/// Размер записи до описания: MAGIC, RECORD_SIZE и поля до DESC_LEN включительно
+pub(crate) const RECORD_HEAD_SIZE: usize = 8 + RECORD_SIZE_WITHOUT_DESC as usize;
+/// Размер CRC32 записи в формате v2
+pub(crate) const CHECKSUM_SIZE: usize = 4;
+/// Размер завершающего блока v2: MAGIC, RECORD_COUNT (u64), CHECKSUM (u32)
+pub(crate) const TRAILER_SIZE: usize = 4 + 8 + 4;
+
+/// Последовательное чтение значений из среза байтов, который начинается
+/// на смещении `offset` в файле. Если данных не хватает, возвращается
+/// [`BinError::InvalidLength`] со смещением недочитанного значения
+struct Cursor<'a> {
+    data: &'a [u8],
+    position: usize,
+    offset: u64,
+    index: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(data: &'a [u8], offset: u64, index: usize) -> Self {
+        Self {
+            data,
+            position: 0,
+            offset,
+            index,
+        }
+    }
+
+    /// Смещение следующего значения в файле
+    fn offset(&self) -> u64 {
+        self.offset + self.position as u64
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], BinError> {
+        let value = self
+            .data
+            .get(self.position..self.position + N)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(BinError::InvalidLength {
+                index: self.index,
+                offset: self.offset(),
+            })?;
+        self.position += N;
+        Ok(value)
+    }
+}
+
+/// Разбор заголовка v2 в начале файла. Для файлов v1 возвращается `None`.
+/// `data` может быть короче заголовка, если файл обрывается
+pub(crate) fn decode_header(data: &[u8]) -> Result<Option<BinHeader>, BinError> {
+    if !data.starts_with(FILE_MAGIC) {
+        return Ok(None);
+    }
+    let truncated = |e| match e {
+        BinError::InvalidLength { offset, .. } => BinError::InvalidHeader { offset },
+        e => e,
+    };
+    let mut cursor = Cursor::new(data, 0, 0);
+    cursor.take::<4>()?;
+    let version_offset = cursor.offset();
+    let version = u16::from_be_bytes(cursor.take().map_err(truncated)?);
+    if version != BinVersion::V2.number() {
+        return Err(BinError::UnsupportedVersion {
+            version,
+            offset: version_offset,
+        });
+    }
+    let record_count = u64::from_be_bytes(cursor.take().map_err(truncated)?);
+    let created_at = i64::from_be_bytes(cursor.take().map_err(truncated)?);
+
+    Ok(Some(BinHeader {
+        version: BinVersion::V2,
+        record_count: header::record_count(record_count),
+        created_at,
+    }))
+}
+
+/// Проверка завершающего блока v2 на смещении `offset`.
+/// `trailer` начинается с MAGIC и может быть короче блока, если файл обрывается,
+/// `checksum` - контрольная сумма всех байтов до завершающего блока,
+/// `actual` - количество прочитанных записей
+pub(crate) fn check_trailer(
+    trailer: &[u8],
+    offset: u64,
+    checksum: u32,
+    header: Option<&BinHeader>,
+    actual: usize,
+) -> Result<(), BinError> {
+    let truncated = |e| match e {
+        BinError::InvalidLength { .. } => BinError::MissingTrailer { offset },
+        e => e,
+    };
+    let mut cursor = Cursor::new(trailer, offset, 0);
+    cursor.take::<4>().map_err(truncated)?;
+    let record_count = u64::from_be_bytes(cursor.take().map_err(truncated)?);
+    let expected = u32::from_be_bytes(cursor.take().map_err(truncated)?);
+    if expected != checksum {
+        return Err(BinError::InvalidFileChecksum { offset });
+    }
+
+    let actual = actual as u64;
+    let declared = header.and_then(|h| h.record_count);
+    for expected in [Some(record_count), declared].into_iter().flatten() {
+        if expected != actual {
+            return Err(BinError::RecordCountMismatch { expected, actual });
+        }
+    }
+    Ok(())
+}
+
+/// Поля записи до описания, значения которых ещё не проверены
+pub(crate) struct RecordHead {
+    index: usize,
+    offset: u64,
+    tx_id: u64,
+    tx_type: u8,
+    tx_type_offset: u64,
+    from_user_id: u64,
+    to_user_id: u64,
+    amount: u64,
+    timestamp: i64,
+    status: u8,
+    status_offset: u64,
+    /// Длина описания в байтах
+    pub(crate) desc_len: usize,
+}
+
+impl RecordHead {
+    /// Разбор записи `index` на смещении `offset` от MAGIC до DESC_LEN включительно.
+    /// `data` может быть короче [`RECORD_HEAD_SIZE`], если файл обрывается
+    pub(crate) fn decode(data: &[u8], index: usize, offset: u64) -> Result<Self, BinError> {
+        let mut cursor = Cursor::new(data, offset, index);
+        if &cursor.take::<4>()? != MAGIC {
+            return Err(BinError::InvalidMagic { index, offset });
+        }
+        let record_size_offset = cursor.offset();
+        let record_size = u32::from_be_bytes(cursor.take()?);
+
+        let tx_id = u64::from_be_bytes(cursor.take()?);
+        let tx_type_offset = cursor.offset();
+        let [tx_type] = cursor.take()?;
+        let from_user_id = u64::from_be_bytes(cursor.take()?);
+        let to_user_id = u64::from_be_bytes(cursor.take()?);
+        let amount = u64::from_be_bytes(cursor.take()?);
+        let timestamp = i64::from_be_bytes(cursor.take()?);
+        let status_offset = cursor.offset();
+        let [status] = cursor.take()?;
+        let desc_len_offset = cursor.offset();
+        let desc_len = i32::from_be_bytes(cursor.take()?);
+
+        let desc_len = u32::try_from(desc_len).map_err(|_| BinError::InvalidDescLen {
+            index,
+            offset: desc_len_offset,
+        })?;
+        if RECORD_SIZE_WITHOUT_DESC.checked_add(desc_len) != Some(record_size) {
+            return Err(BinError::InvalidRecordSize {
+                index,
+                offset: record_size_offset,
+            });
+        }
+
+        Ok(Self {
+            index,
+            offset,
+            tx_id,
+            tx_type,
+            tx_type_offset,
+            from_user_id,
+            to_user_id,
+            amount,
+            timestamp,
+            status,
+            status_offset,
+            desc_len: desc_len as usize,
+        })
+    }
+
+    /// Проверка CRC32 и значений полей записи.
+    /// `record` - байты записи от MAGIC до конца описания, `checksum` - CRC32 записи v2
+    pub(crate) fn finish<'a>(
+        &self,
+        record: &'a [u8],
+        checksum: Option<u32>,
+    ) -> Result<TransactionRef<'a>, BinError> {
+        let index = self.index;
+        if let Some(checksum) = checksum
+            && crc32fast::hash(record) != checksum
+        {
+            return Err(BinError::InvalidChecksum {
+                index,
+                offset: self.offset,
+            });
+        }
+
+        let tx_type = decode_tx_type(self.tx_type).ok_or(BinError::InvalidField {
+            index,
+            offset: self.tx_type_offset,
+            field: Field::TxType,
+        })?;
+        let status = decode_status(self.status).ok_or(BinError::InvalidField {
+            index,
+            offset: self.status_offset,
+            field: Field::Status,
+        })?;
+        let description_offset = self.offset + RECORD_HEAD_SIZE as u64;
+        let description =
+            str::from_utf8(&record[RECORD_HEAD_SIZE..]).map_err(|source| BinError::Utf8 {
+                index,
+                offset: description_offset + source.valid_up_to() as u64,
+                source,
+            })?;
+        let description = description_trim(description).map_err(|_| BinError::InvalidField {
+            index,
+            offset: description_offset,
+            field: Field::Description,
+        })?;
+
+        Ok(TransactionRef {
+            tx_id: self.tx_id,
+            tx_type,
+            from_user_id: self.from_user_id,
+            to_user_id: self.to_user_id,
+            amount: self.amount,
+            timestamp: self.timestamp,
+            status,
+            description,
+        })
+    }
+}
+
+fn decode_tx_type(value: u8) -> Option<TxType> {
+    match value {
+        0 => Some(TxType::Deposit),
+        1 => Some(TxType::Transfer),
+        2 => Some(TxType::Withdrawal),
+        _ => None,
+    }
+}
+
+fn decode_status(value: u8) -> Option<Status> {
+    match value {
+        0 => Some(Status::Success),
+        1 => Some(Status::Failure),
+        2 => Some(Status::Pending),
+        _ => None,
+    }
+}
+
+/// Ошибки, после которых можно продолжить чтение со следующей записи
+pub(crate) fn is_recoverable(error: &BinError) -> bool {
+    matches!(
+        error,
+        BinError::InvalidField { .. }
+            | BinError::Utf8 { .. }
+            | BinError::DescriptionTooLong { .. }
+            | BinError::InvalidChecksum { .. }
+    )
+}
+
+/// Ошибки в структуре записи, после которых границе записи нельзя доверять
+/// и следующую запись нужно искать по MAGIC
+pub(crate) fn needs_resync(error: &BinError) -> bool {
+    matches!(
+        error,
+        BinError::InvalidMagic { .. }
+            | BinError::InvalidRecordSize { .. }
+            | BinError::InvalidDescLen { .. }
+            | BinError::InvalidLength { .. }
+    )
+}
@@ -1,3 +1,5 @@
+use std::{io, str::Utf8Error};
+
 use thiserror::Error;
 
 use crate::Field;
@@ -9,38 +11,157 @@ pub enum BinError {
     #[error("Неизвестная ошибка")]
     Unknown,
     /// Ошибка чтения
-    #[error("Ошибка чтения")]
-    Read,
+    #[error("Ошибка чтения на смещении {offset}: {source}")]
+    Read {
+        /// Смещение от начала файла в байтах
+        offset: u64,
+        /// Исходная ошибка ввода-вывода
+        #[source]
+        source: io::Error,
+    },
     /// Неожиданное завершение записи
-    #[error("Неожиданное завершение записи {index}")]
+    #[error("Неожиданное завершение записи {index} на смещении {offset}")]
     InvalidLength {
         /// Индекс строки
         index: usize,
+        /// Смещение от начала файла в байтах
+        offset: u64,
     },
     /// Некорректный MAGIC
-    #[error("Некорректный MAGIC в записи {index}")]
+    #[error("Некорректный MAGIC в записи {index} на смещении {offset}")]
     InvalidMagic {
         /// Индекс строки
         index: usize,
+        /// Смещение от начала файла в байтах
+        offset: u64,
     },
     /// Некорректный RECORD_SIZE
-    #[error("Некорректный RECORD_SIZE в записи {index}")]
+    #[error("Некорректный RECORD_SIZE в записи {index} на смещении {offset}")]
     InvalidRecordSize {
         /// Индекс строки
         index: usize,
+        /// Смещение от начала файла в байтах
+        offset: u64,
     },
     /// Некорректное поле
-    #[error("Ошибка парсинга поля {field} в записи {index}")]
+    #[error("Ошибка парсинга поля {field} в записи {index} на смещении {offset}")]
     InvalidField {
         /// Индекс строки
         index: usize,
+        /// Смещение от начала файла в байтах
+        offset: u64,
         /// Поле
         field: Field,
     },
+    /// Описание не является корректной UTF-8 последовательностью
+    #[error("Некорректная кодировка UTF-8 в описании записи {index} на смещении {offset}")]
+    Utf8 {
+        /// Индекс строки
+        index: usize,
+        /// Смещение от начала файла в байтах
+        offset: u64,
+        /// Исходная ошибка декодирования
+        #[source]
+        source: Utf8Error,
+    },
     /// Некорректный DESC_LEN
-    #[error("Некорректный DESC_LEN в записи {index}")]
+    #[error("Некорректный DESC_LEN в записи {index} на смещении {offset}")]
     InvalidDescLen {
         /// Индекс строки
         index: usize,
+        /// Смещение от начала файла в байтах
+        offset: u64,
+    },
+    /// Превышен максимальный размер входных данных
+    #[error("Превышен максимальный размер входных данных {limit} байт на смещении {offset}")]
+    InputTooLarge {
+        /// Смещение от начала файла в байтах
+        offset: u64,
+        /// Ограничение
+        limit: u64,
+    },
+    /// Превышено максимальное количество записей
+    #[error("Превышено максимальное количество записей {limit}")]
+    TooManyRecords {
+        /// Ограничение
+        limit: usize,
+    },
+    /// Превышена максимальная длина описания
+    #[error(
+        "Превышена максимальная длина описания {limit} байт в записи {index} на смещении {offset}"
+    )]
+    DescriptionTooLong {
+        /// Индекс строки
+        index: usize,
+        /// Смещение от начала файла в байтах
+        offset: u64,
+        /// Ограничение
+        limit: usize,
+    },
+    /// Некорректный заголовок файла
+    #[error("Некорректный заголовок файла на смещении {offset}")]
+    InvalidHeader {
+        /// Смещение от начала файла в байтах
+        offset: u64,
+    },
+    /// Неподдерживаемая версия формата
+    #[error("Неподдерживаемая версия формата {version} на смещении {offset}")]
+    UnsupportedVersion {
+        /// Версия из заголовка файла
+        version: u16,
+        /// Смещение от начала файла в байтах
+        offset: u64,
+    },
+    /// Контрольная сумма записи не совпадает с содержимым
+    #[error("Некорректная контрольная сумма записи {index} на смещении {offset}")]
+    InvalidChecksum {
+        /// Индекс строки
+        index: usize,
+        /// Смещение от начала файла в байтах
+        offset: u64,
+    },
+    /// Файл закончился без завершающего блока
+    #[error("Отсутствует завершающий блок файла на смещении {offset}")]
+    MissingTrailer {
+        /// Смещение от начала файла в байтах
+        offset: u64,
+    },
+    /// Контрольная сумма файла не совпадает с содержимым
+    #[error("Некорректная контрольная сумма файла на смещении {offset}")]
+    InvalidFileChecksum {
+        /// Смещение от начала файла в байтах
+        offset: u64,
+    },
+    /// Количество записей не совпадает с заявленным
+    #[error("Количество записей {actual} не совпадает с заявленным {expected}")]
+    RecordCountMismatch {
+        /// Количество записей из заголовка или завершающего блока
+        expected: u64,
+        /// Количество прочитанных записей
+        actual: u64,
+    },
+    /// Данные после завершающего блока
+    #[error("Данные после завершающего блока на смещении {offset}")]
+    TrailingData {
+        /// Смещение от начала файла в байтах
+        offset: u64,
+    },
+    /// Некорректный файл индекса
+    #[error("Некорректный файл индекса на смещении {offset}")]
+    InvalidIndex {
+        /// Смещение от начала файла индекса в байтах
+        offset: u64,
+    },
+    /// Запись по смещению из индекса не соответствует индексу
+    #[error(
+        "Индекс устарел: на смещении {offset} ожидалась транзакция {expected}, найдена {actual}"
+    )]
+    StaleIndex {
+        /// TX_ID из индекса
+        expected: u64,
+        /// TX_ID прочитанной записи
+        actual: u64,
+        /// Смещение от начала файла в байтах
+        offset: u64,
     },
 }
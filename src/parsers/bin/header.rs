@@ -0,0 +1,68 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// MAGIC заголовка файла bin v2
+pub(crate) const FILE_MAGIC: &[u8; 4] = b"YPBF";
+/// MAGIC завершающего блока файла bin v2
+pub(crate) const TRAILER_MAGIC: &[u8; 4] = b"YPBE";
+/// Значение RECORD_COUNT в заголовке, если количество записей заранее неизвестно
+const UNKNOWN_RECORD_COUNT: u64 = u64::MAX;
+
+/// Версия bin формата
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum BinVersion {
+    /// Последовательность записей без заголовка
+    #[default]
+    V1,
+    /// Заголовок файла, CRC32 каждой записи и завершающий блок с контрольной суммой файла
+    V2,
+}
+
+impl BinVersion {
+    pub(crate) fn number(&self) -> u16 {
+        match self {
+            BinVersion::V1 => 1,
+            BinVersion::V2 => 2,
+        }
+    }
+}
+
+/// Заголовок файла bin v2
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BinHeader {
+    /// Версия формата
+    pub version: BinVersion,
+    /// Количество записей, если оно было известно при создании файла
+    pub record_count: Option<u64>,
+    /// Время создания файла в формате Unix-времени в миллисекундах
+    pub created_at: i64,
+}
+
+impl BinHeader {
+    /// Размер заголовка: MAGIC, VERSION (u16), RECORD_COUNT (u64), CREATED_AT (i64)
+    pub(crate) const SIZE: usize = 4 + 2 + 8 + 8;
+
+    pub(crate) fn to_bytes(self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(Self::SIZE);
+        data.extend_from_slice(FILE_MAGIC);
+        data.extend_from_slice(&self.version.number().to_be_bytes());
+        data.extend_from_slice(
+            &self
+                .record_count
+                .unwrap_or(UNKNOWN_RECORD_COUNT)
+                .to_be_bytes(),
+        );
+        data.extend_from_slice(&self.created_at.to_be_bytes());
+        data
+    }
+}
+
+pub(crate) fn record_count(value: u64) -> Option<u64> {
+    (value != UNKNOWN_RECORD_COUNT).then_some(value)
+}
+
+pub(crate) fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
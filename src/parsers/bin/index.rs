@@ -0,0 +1,544 @@
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use crate::{
+    Transaction,
+    atomic::AtomicFile,
+    errors::{ParserError, WriteError},
+    parsers::bin::{
+        decode::CHECKSUM_SIZE,
+        error::BinError,
+        header::{BinHeader, BinVersion, FILE_MAGIC, TRAILER_MAGIC},
+        parser::{BinRecords, MAGIC, RECORD_SIZE_WITHOUT_DESC},
+    },
+};
+
+/// MAGIC файла индекса
+const INDEX_MAGIC: &[u8; 4] = b"YPBI";
+/// Размер начала записи, из которого берутся MAGIC, RECORD_SIZE и TX_ID
+const RECORD_HEAD_SIZE: usize = 4 + 4 + 8;
+
+/// Положение записи в файле
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IndexEntry {
+    /// Идентификатор транзакции
+    pub tx_id: u64,
+    /// Смещение начала записи от начала файла в байтах
+    pub offset: u64,
+}
+
+/// Индекс bin файла: смещения записей по их номеру и `tx_id`.
+///
+/// Индекс хранится рядом с файлом в `<файл>.idx`:
+/// MAGIC `YPBI`, VERSION (u16), FILE_LEN (u64), MODIFIED (u64), DATA_END (u64), COUNT (u64),
+/// затем COUNT пар TX_ID (u64), OFFSET (u64)
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinIndex {
+    version: BinVersion,
+    /// Размер проиндексированного файла, по нему определяется устаревший индекс
+    file_len: u64,
+    /// Время изменения проиндексированного файла в наносекундах от UNIX_EPOCH
+    /// или 0, если оно неизвестно. Также используется для определения устаревшего индекса
+    modified: u64,
+    /// Смещение конца последней записи
+    data_end: u64,
+    entries: Vec<IndexEntry>,
+    /// Номера записей, отсортированные по `tx_id`
+    by_tx_id: Vec<usize>,
+}
+
+impl BinIndex {
+    fn new(version: BinVersion, file_len: u64, data_end: u64, entries: Vec<IndexEntry>) -> Self {
+        let mut by_tx_id: Vec<usize> = (0..entries.len()).collect();
+        by_tx_id.sort_by_key(|&n| entries[n].tx_id);
+        Self {
+            version,
+            file_len,
+            modified: 0,
+            data_end,
+            entries,
+            by_tx_id,
+        }
+    }
+
+    /// Построение индекса по заголовкам записей без чтения их содержимого
+    pub fn scan<R: Read + Seek>(reader: &mut R) -> Result<Self, BinError> {
+        let read_error = |offset| move |source| BinError::Read { offset, source };
+
+        let file_len = reader.seek(SeekFrom::End(0)).map_err(read_error(0))?;
+        reader.seek(SeekFrom::Start(0)).map_err(read_error(0))?;
+        let mut reader = BufReader::new(reader);
+
+        let mut head = [0; RECORD_HEAD_SIZE];
+        let mut version = BinVersion::V1;
+        let mut offset = 0;
+        let n = read_head(&mut reader, &mut head[..FILE_MAGIC.len()]).map_err(read_error(0))?;
+        if n == FILE_MAGIC.len() && head[..n] == FILE_MAGIC[..] {
+            let mut number = [0; 2];
+            reader
+                .read_exact(&mut number)
+                .map_err(|_| BinError::InvalidHeader { offset: 4 })?;
+            let number = u16::from_be_bytes(number);
+            if number != BinVersion::V2.number() {
+                return Err(BinError::UnsupportedVersion {
+                    version: number,
+                    offset: 4,
+                });
+            }
+            version = BinVersion::V2;
+            offset = BinHeader::SIZE as u64;
+        }
+        reader
+            .seek(SeekFrom::Start(offset))
+            .map_err(read_error(offset))?;
+
+        let mut entries = Vec::new();
+        let data_end = loop {
+            let index = entries.len();
+            let n = read_head(&mut reader, &mut head).map_err(read_error(offset))?;
+            if n == 0 {
+                if version == BinVersion::V2 {
+                    return Err(BinError::MissingTrailer { offset });
+                }
+                break offset;
+            }
+            if version == BinVersion::V2 && head[..n].starts_with(TRAILER_MAGIC) {
+                break offset;
+            }
+            if n < RECORD_HEAD_SIZE {
+                return Err(BinError::InvalidLength { index, offset });
+            }
+            if head[..4] != MAGIC[..] {
+                return Err(BinError::InvalidMagic { index, offset });
+            }
+
+            let record_size = u32::from_be_bytes([head[4], head[5], head[6], head[7]]);
+            if record_size < RECORD_SIZE_WITHOUT_DESC {
+                return Err(BinError::InvalidRecordSize {
+                    index,
+                    offset: offset + 4,
+                });
+            }
+            let mut len = 8 + record_size as u64;
+            if version == BinVersion::V2 {
+                len += CHECKSUM_SIZE as u64;
+            }
+            if offset + len > file_len {
+                return Err(BinError::InvalidLength { index, offset });
+            }
+
+            entries.push(IndexEntry {
+                tx_id: u64::from_be_bytes(head[8..16].try_into().unwrap_or_default()),
+                offset,
+            });
+            reader
+                .seek_relative((len - RECORD_HEAD_SIZE as u64) as i64)
+                .map_err(read_error(offset))?;
+            offset += len;
+        };
+
+        Ok(Self::new(version, file_len, data_end, entries))
+    }
+
+    /// Загрузка индекса из `<файл>.idx` или его построение, если файл индекса
+    /// отсутствует, повреждён или устарел. Индекс считается устаревшим, если размер
+    /// или время изменения файла отличаются от сохранённых в индексе.
+    /// Построенный индекс сохраняется рядом с файлом. Если сохранить его не удалось,
+    /// например в каталоге только для чтения, индекс всё равно возвращается
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ParserError> {
+        let path = path.as_ref();
+        let mut file = File::open(path).map_err(ParserError::Read)?;
+        let metadata = file.metadata().map_err(ParserError::Read)?;
+        let file_len = metadata.len();
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_nanos() as u64);
+
+        let index_path = Self::index_path(path);
+        if let Ok(index_file) = File::open(&index_path)
+            && let Ok(index) = Self::read_from(&mut BufReader::new(index_file))
+            && index.file_len == file_len
+            && index.modified == modified
+        {
+            return Ok(index);
+        }
+
+        let mut index = Self::scan(&mut file)?;
+        index.modified = modified;
+        let _ = index.save(&index_path);
+        Ok(index)
+    }
+
+    /// Сохранение индекса через временный файл, чтобы при сбое не остался обрезанный индекс
+    fn save(&self, index_path: &Path) -> Result<(), WriteError> {
+        let mut file = AtomicFile::create(index_path)?;
+        self.write_to(&mut file)?;
+        file.commit()?;
+        Ok(())
+    }
+
+    /// Путь к файлу индекса: `<файл>.idx`
+    pub fn index_path(path: impl AsRef<Path>) -> PathBuf {
+        let mut index_path = OsString::from(path.as_ref());
+        index_path.push(".idx");
+        PathBuf::from(index_path)
+    }
+
+    /// Чтение индекса
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self, BinError> {
+        let mut offset = 0;
+        if &read_array::<4>(reader, &mut offset)? != INDEX_MAGIC {
+            return Err(BinError::InvalidIndex { offset: 0 });
+        }
+        let version = match u16::from_be_bytes(read_array(reader, &mut offset)?) {
+            1 => BinVersion::V1,
+            2 => BinVersion::V2,
+            _ => return Err(BinError::InvalidIndex { offset: 4 }),
+        };
+        let file_len = u64::from_be_bytes(read_array(reader, &mut offset)?);
+        let modified = u64::from_be_bytes(read_array(reader, &mut offset)?);
+        let data_end = u64::from_be_bytes(read_array(reader, &mut offset)?);
+        let count = u64::from_be_bytes(read_array(reader, &mut offset)?);
+
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let entry_offset = offset;
+            let tx_id = u64::from_be_bytes(read_array(reader, &mut offset)?);
+            let record_offset = u64::from_be_bytes(read_array(reader, &mut offset)?);
+            if record_offset >= data_end {
+                return Err(BinError::InvalidIndex {
+                    offset: entry_offset,
+                });
+            }
+            entries.push(IndexEntry {
+                tx_id,
+                offset: record_offset,
+            });
+        }
+
+        Ok(Self {
+            modified,
+            ..Self::new(version, file_len, data_end, entries)
+        })
+    }
+
+    /// Запись индекса
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), WriteError> {
+        writer.write_all(INDEX_MAGIC)?;
+        writer.write_all(&self.version.number().to_be_bytes())?;
+        writer.write_all(&self.file_len.to_be_bytes())?;
+        writer.write_all(&self.modified.to_be_bytes())?;
+        writer.write_all(&self.data_end.to_be_bytes())?;
+        writer.write_all(&(self.entries.len() as u64).to_be_bytes())?;
+        for entry in &self.entries {
+            writer.write_all(&entry.tx_id.to_be_bytes())?;
+            writer.write_all(&entry.offset.to_be_bytes())?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Версия формата проиндексированного файла
+    pub fn version(&self) -> BinVersion {
+        self.version
+    }
+
+    /// Количество записей
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Нет ни одной записи
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Положения записей в порядке следования в файле
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// Номер первой записи с заданным `tx_id`
+    pub fn find(&self, tx_id: u64) -> Option<usize> {
+        let position = self
+            .by_tx_id
+            .partition_point(|&n| self.entries[n].tx_id < tx_id);
+        self.by_tx_id
+            .get(position)
+            .copied()
+            .filter(|&n| self.entries[n].tx_id == tx_id)
+    }
+
+    /// Смещение начала записи `n` или конца последней записи
+    fn offset(&self, n: usize) -> u64 {
+        self.entries.get(n).map_or(self.data_end, |e| e.offset)
+    }
+}
+
+/// Чтение в буфер до его заполнения или конца потока
+fn read_head<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn read_array<const N: usize>(
+    reader: &mut impl Read,
+    offset: &mut u64,
+) -> Result<[u8; N], BinError> {
+    let mut value = [0; N];
+    reader
+        .read_exact(&mut value)
+        .map_err(|_| BinError::InvalidIndex { offset: *offset })?;
+    *offset += N as u64;
+    Ok(value)
+}
+
+/// Произвольный доступ к записям bin файла по индексу
+pub struct BinRandomReader<R> {
+    reader: R,
+    index: BinIndex,
+}
+
+impl<R: Read + Seek> BinRandomReader<R> {
+    /// Создание читателя с готовым индексом
+    pub fn new(reader: R, index: BinIndex) -> Self {
+        Self { reader, index }
+    }
+
+    /// Создание читателя с построением индекса
+    pub fn scan(mut reader: R) -> Result<Self, BinError> {
+        let index = BinIndex::scan(&mut reader)?;
+        Ok(Self { reader, index })
+    }
+
+    /// Индекс файла
+    pub fn index(&self) -> &BinIndex {
+        &self.index
+    }
+
+    /// Получение исходного потока
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Чтение записи по её номеру
+    pub fn get(&mut self, n: usize) -> Result<Option<Transaction>, BinError> {
+        if n >= self.index.len() {
+            return Ok(None);
+        }
+        self.range(n..n + 1)?.next().transpose()
+    }
+
+    /// Чтение первой записи с заданным `tx_id`.
+    /// Если по смещению из индекса находится другая транзакция, возвращается
+    /// [`BinError::StaleIndex`]
+    pub fn find_by_tx_id(&mut self, tx_id: u64) -> Result<Option<Transaction>, BinError> {
+        let Some(n) = self.index.find(tx_id) else {
+            return Ok(None);
+        };
+        let transaction = self.get(n)?;
+        if let Some(t) = &transaction
+            && t.tx_id != tx_id
+        {
+            return Err(BinError::StaleIndex {
+                expected: tx_id,
+                actual: t.tx_id,
+                offset: self.index.offset(n),
+            });
+        }
+        Ok(transaction)
+    }
+
+    /// Потоковое чтение записей с номерами из `range`.
+    /// Номера за пределами файла отбрасываются
+    pub fn range(
+        &mut self,
+        range: Range<usize>,
+    ) -> Result<impl Iterator<Item = Result<Transaction, BinError>> + '_, BinError> {
+        let end = range.end.min(self.index.len());
+        let start = range.start.min(end);
+        let offset = self.index.offset(start);
+        let len = self.index.offset(end) - offset;
+
+        self.reader
+            .seek(SeekFrom::Start(offset))
+            .map_err(|source| BinError::Read { offset, source })?;
+        let reader = BufReader::new((&mut self.reader).take(len));
+        Ok(BinRecords::new(reader).with_position(self.index.version, offset, start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, time::Duration};
+
+    use super::*;
+    use crate::{
+        Parser, TransactionWriter,
+        parsers::bin::parser::{BinParser, BinWriter},
+    };
+
+    fn get_transactions() -> Vec<Transaction> {
+        (0..5)
+            .map(|n| Transaction {
+                tx_id: 100 - n,
+                description: "x".repeat(n as usize),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn write(version: BinVersion) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut writer = BinWriter::new(&mut data).with_version(version);
+        for t in &get_transactions() {
+            writer.write(t).unwrap();
+        }
+        writer.finish().unwrap();
+        data
+    }
+
+    #[test]
+    fn test_success_random_reader() {
+        let transactions = get_transactions();
+        for version in [BinVersion::V1, BinVersion::V2] {
+            let mut reader = BinRandomReader::scan(Cursor::new(write(version))).unwrap();
+            assert_eq!(reader.index().version(), version);
+            assert_eq!(reader.index().len(), 5);
+
+            assert_eq!(reader.get(3).unwrap().unwrap(), transactions[3]);
+            assert_eq!(reader.get(0).unwrap().unwrap(), transactions[0]);
+            assert!(reader.get(5).unwrap().is_none());
+
+            assert_eq!(reader.find_by_tx_id(98).unwrap().unwrap(), transactions[2]);
+            assert!(reader.find_by_tx_id(1).unwrap().is_none());
+
+            let range: Vec<Transaction> = reader
+                .range(1..10)
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(range, transactions[1..]);
+        }
+    }
+
+    #[test]
+    fn test_success_index_round_trip() {
+        let index = BinIndex::scan(&mut Cursor::new(write(BinVersion::V2))).unwrap();
+        let mut data = Vec::new();
+        index.write_to(&mut data).unwrap();
+        assert_eq!(BinIndex::read_from(&mut Cursor::new(&data)).unwrap(), index);
+
+        let mut truncated = data.clone();
+        truncated.truncate(data.len() - 1);
+        assert_eq!(
+            BinIndex::read_from(&mut Cursor::new(&truncated))
+                .unwrap_err()
+                .to_string(),
+            "Некорректный файл индекса на смещении 110"
+        );
+
+        data[22..30].fill(0); // DATA_END
+        assert_eq!(
+            BinIndex::read_from(&mut Cursor::new(&data))
+                .unwrap_err()
+                .to_string(),
+            "Некорректный файл индекса на смещении 38"
+        );
+    }
+
+    #[test]
+    fn test_success_open() {
+        let path = std::env::temp_dir().join(format!("bin_index_{}.bin", std::process::id()));
+        let index_path = BinIndex::index_path(&path);
+        assert!(index_path.to_string_lossy().ends_with(".bin.idx"));
+
+        std::fs::write(&path, write(BinVersion::V2)).unwrap();
+        let index = BinIndex::open(&path).unwrap();
+        assert!(index_path.exists());
+        assert_eq!(BinIndex::open(&path).unwrap(), index);
+
+        // после изменения файла индекс строится заново
+        let mut data = Vec::new();
+        BinParser::write_to(&mut data, &get_transactions()[..2]).unwrap();
+        std::fs::write(&path, data).unwrap();
+        assert_eq!(BinIndex::open(&path).unwrap().len(), 2);
+
+        // в том числе если размер файла не изменился
+        let mut transactions = get_transactions()[..2].to_vec();
+        transactions[0].tx_id = 1;
+        let mut data = Vec::new();
+        BinParser::write_to(&mut data, &transactions).unwrap();
+        std::fs::write(&path, data).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(UNIX_EPOCH + Duration::from_secs(1))
+            .unwrap();
+        let index = BinIndex::open(&path).unwrap();
+        assert_eq!(index.find(1), Some(0));
+        assert_eq!(index.find(100), None);
+
+        // индекс, который не удалось сохранить, всё равно возвращается
+        std::fs::remove_file(&index_path).unwrap();
+        std::fs::create_dir(&index_path).unwrap();
+        assert_eq!(BinIndex::open(&path).unwrap().len(), 2);
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_dir(&index_path).unwrap();
+    }
+
+    #[test]
+    fn test_error_stale_index() {
+        let index = BinIndex::scan(&mut Cursor::new(write(BinVersion::V1))).unwrap();
+        let mut transactions = get_transactions();
+        transactions[2].tx_id = 1;
+        let mut data = Vec::new();
+        BinParser::write_to(&mut data, &transactions).unwrap();
+
+        let mut reader = BinRandomReader::new(Cursor::new(data), index);
+        assert_eq!(reader.find_by_tx_id(99).unwrap().unwrap(), transactions[1]);
+        assert_eq!(
+            reader.find_by_tx_id(98).unwrap_err().to_string(),
+            "Индекс устарел: на смещении 113 ожидалась транзакция 98, найдена 1"
+        );
+    }
+
+    #[test]
+    fn test_error_scan() {
+        let mut data = write(BinVersion::V1);
+        data.truncate(data.len() - 1);
+        assert_eq!(
+            BinIndex::scan(&mut Cursor::new(data))
+                .unwrap_err()
+                .to_string(),
+            "Неожиданное завершение записи 4 на смещении 230"
+        );
+
+        let mut data = write(BinVersion::V2);
+        data.truncate(data.len() - 16);
+        assert_eq!(
+            BinIndex::scan(&mut Cursor::new(data))
+                .unwrap_err()
+                .to_string(),
+            "Отсутствует завершающий блок файла на смещении 332"
+        );
+    }
+}
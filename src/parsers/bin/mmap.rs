@@ -0,0 +1,395 @@
+use std::{fs::File, path::Path};
+
+use memmap2::Mmap;
+
+use crate::{
+    ParseLimits, ParseMode, TransactionRef,
+    errors::ParserError,
+    parsers::bin::{
+        decode::{
+            CHECKSUM_SIZE, RECORD_HEAD_SIZE, RecordHead, TRAILER_SIZE, check_trailer,
+            decode_header, is_recoverable, needs_resync,
+        },
+        error::BinError,
+        header::{BinHeader, BinVersion, FILE_MAGIC, TRAILER_MAGIC},
+        parser::MAGIC,
+    },
+};
+
+/// Bin файл, отображённый в память
+pub struct BinMmap {
+    mmap: Mmap,
+}
+
+impl BinMmap {
+    /// Отображение файла в память
+    ///
+    /// # Safety
+    ///
+    /// Файл не должен изменяться или усекаться ни этим, ни другими процессами,
+    /// пока существует отображение. Иначе срезы, полученные из [`BinMmap::as_bytes`]
+    /// и [`BinMmap::records`], могут измениться во время чтения, а обращение
+    /// к усечённой части файла завершит процесс сигналом `SIGBUS`
+    pub unsafe fn open(path: impl AsRef<Path>) -> Result<Self, ParserError> {
+        let file = File::open(path).map_err(ParserError::Read)?;
+        // SAFETY: неизменность файла на время жизни отображения гарантирует вызывающий
+        let mmap = unsafe { Mmap::map(&file) }.map_err(ParserError::Read)?;
+        Ok(Self { mmap })
+    }
+
+    /// Содержимое файла
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    /// Чтение записей без копирования описаний
+    pub fn records(&self) -> BinSliceRecords<'_> {
+        BinSliceRecords::new(&self.mmap)
+    }
+}
+
+/// Чтение записей bin формата из среза байтов.
+/// Описания транзакций ссылаются на исходные данные. Как и в
+/// [`crate::parsers::bin::parser::BinRecords`], в режиме [`ParseMode::Lenient`]
+/// после повреждённой записи чтение продолжается со следующего найденного MAGIC
+pub struct BinSliceRecords<'a> {
+    data: &'a [u8],
+    mode: ParseMode,
+    limits: ParseLimits,
+    offset: usize,
+    /// Смещение начала текущей записи
+    record_offset: usize,
+    record_index: usize,
+    version: BinVersion,
+    header: Option<BinHeader>,
+    started: bool,
+    count: usize,
+    finished: bool,
+}
+
+impl<'a> BinSliceRecords<'a> {
+    /// Создание итератора по записям
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            mode: ParseMode::Strict,
+            limits: ParseLimits::default(),
+            offset: 0,
+            record_offset: 0,
+            record_index: 0,
+            version: BinVersion::V1,
+            header: None,
+            started: false,
+            count: 0,
+            finished: false,
+        }
+    }
+
+    /// Установка режима разбора
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Установка ограничений на размер входных данных
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Заголовок файла. Для файлов v1 отсутствует
+    pub fn header(&self) -> Option<&BinHeader> {
+        self.header.as_ref()
+    }
+
+    /// До `n` байт начиная с текущего смещения.
+    /// Меньше `n` байт возвращается, только если данные закончились
+    fn take_upto(&mut self, n: usize) -> Result<&'a [u8], BinError> {
+        let end = self.offset.saturating_add(n).min(self.data.len());
+        if let Some(limit) = self.limits.max_input_bytes
+            && end as u64 > limit
+        {
+            return Err(BinError::InputTooLarge {
+                offset: self.offset as u64,
+                limit,
+            });
+        }
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    /// Ровно `n` байт начиная с текущего смещения
+    fn take(&mut self, n: usize) -> Result<&'a [u8], BinError> {
+        let offset = self.offset;
+        let bytes = self.take_upto(n)?;
+        if bytes.len() != n {
+            return Err(BinError::InvalidLength {
+                index: self.record_index,
+                offset: offset as u64,
+            });
+        }
+        Ok(bytes)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], BinError> {
+        let mut value = [0; N];
+        value.copy_from_slice(self.take(N)?);
+        Ok(value)
+    }
+
+    fn read_record(&mut self) -> Result<Option<TransactionRef<'a>>, BinError> {
+        if !self.started {
+            self.started = true;
+            if self.data.starts_with(FILE_MAGIC) {
+                let header = self.take_upto(BinHeader::SIZE)?;
+                self.header = decode_header(header)?;
+                self.version = BinVersion::V2;
+            }
+        }
+
+        let rest = &self.data[self.offset..];
+        if rest.is_empty() {
+            if self.version == BinVersion::V2 {
+                return Err(BinError::MissingTrailer {
+                    offset: self.offset as u64,
+                });
+            }
+            return Ok(None);
+        }
+
+        let record_index = self.record_index;
+        self.record_offset = self.offset;
+        let record_offset = self.offset as u64;
+
+        if self.version == BinVersion::V2 && rest.starts_with(TRAILER_MAGIC) {
+            let checksum = crc32fast::hash(&self.data[..self.offset]);
+            let trailer = self.take_upto(TRAILER_SIZE)?;
+            check_trailer(
+                trailer,
+                record_offset,
+                checksum,
+                self.header.as_ref(),
+                record_index,
+            )?;
+            if self.offset != self.data.len() {
+                return Err(BinError::TrailingData {
+                    offset: self.offset as u64,
+                });
+            }
+            return Ok(None);
+        }
+
+        let head = self.take_upto(RECORD_HEAD_SIZE)?;
+        let head = RecordHead::decode(head, record_index, record_offset)?;
+
+        if let Some(limit) = self.limits.max_description_bytes
+            && head.desc_len > limit
+        {
+            let description_offset = self.offset as u64;
+            self.take(head.desc_len)?;
+            if self.version == BinVersion::V2 {
+                self.take_array::<CHECKSUM_SIZE>()?;
+            }
+            self.record_index += 1;
+            return Err(BinError::DescriptionTooLong {
+                index: record_index,
+                offset: description_offset,
+                limit,
+            });
+        }
+        self.take(head.desc_len)?;
+        let record = &self.data[self.record_offset..self.offset];
+        let checksum = match self.version {
+            BinVersion::V1 => None,
+            BinVersion::V2 => Some(u32::from_be_bytes(self.take_array()?)),
+        };
+
+        // запись прочитана целиком, поэтому после ошибки в значениях полей
+        // чтение можно продолжить со следующей записи
+        self.record_index += 1;
+
+        head.finish(record, checksum).map(Some)
+    }
+
+    /// Переход к следующему MAGIC после начала повреждённой записи
+    /// или к концу данных, если MAGIC не найден
+    fn resync(&mut self) {
+        let start = (self.record_offset + 1).min(self.data.len());
+        self.offset = self.data[start..]
+            .windows(MAGIC.len())
+            .position(|window| {
+                window == MAGIC || (self.version == BinVersion::V2 && window == TRAILER_MAGIC)
+            })
+            .map_or(self.data.len(), |position| start + position);
+    }
+}
+
+impl<'a> Iterator for BinSliceRecords<'a> {
+    type Item = Result<TransactionRef<'a>, BinError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let mut result = self.read_record().transpose();
+        if result.is_some()
+            && let Some(limit) = self.limits.max_records
+            && self.count >= limit
+        {
+            result = Some(Err(BinError::TooManyRecords { limit }));
+        }
+        self.count += 1;
+        match &result {
+            None => self.finished = true,
+            Some(Ok(_)) => {}
+            Some(Err(_)) if self.mode == ParseMode::Strict => self.finished = true,
+            Some(Err(e)) if is_recoverable(e) => {}
+            Some(Err(e)) if needs_resync(e) => {
+                self.record_index += 1;
+                self.resync();
+            }
+            Some(Err(_)) => self.finished = true,
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        Parser, Transaction, TransactionWriter,
+        parsers::bin::parser::{BinParser, BinRecords, BinWriter},
+    };
+
+    fn get_transactions() -> Vec<Transaction> {
+        (0..3)
+            .map(|n| Transaction {
+                tx_id: n,
+                amount: 100 * n,
+                description: format!("record {n}"),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn write_v2() -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut writer = BinWriter::new(&mut data).with_version(BinVersion::V2);
+        for t in &get_transactions() {
+            writer.write(t).unwrap();
+        }
+        writer.finish().unwrap();
+        data
+    }
+
+    #[test]
+    fn test_success_records() {
+        let mut v1 = Vec::new();
+        BinParser::write_to(&mut v1, &get_transactions()).unwrap();
+        let v2 = write_v2();
+
+        for data in [v1, v2] {
+            let records: Vec<TransactionRef> = BinSliceRecords::new(&data)
+                .collect::<Result<_, _>>()
+                .unwrap();
+            let description = records[1].description();
+            assert_eq!(description, "record 1");
+            assert!(data.as_ptr_range().contains(&description.as_ptr()));
+
+            let owned: Vec<Transaction> = records.iter().map(|r| r.to_transaction()).collect();
+            assert_eq!(
+                owned,
+                BinParser::from_read(&mut Cursor::new(&data)).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_success_lenient() {
+        let mut data = write_v2();
+        let record_len = (data.len() - BinHeader::SIZE - 16) / 3;
+        data[BinHeader::SIZE + 40] ^= 1; // AMOUNT первой записи
+        data[BinHeader::SIZE + record_len] = b'X'; // MAGIC второй записи
+
+        // результаты совпадают с потоковым чтением
+        let expected: Vec<_> = BinRecords::new(Cursor::new(&data))
+            .with_mode(ParseMode::Lenient)
+            .map(|r| r.map_err(|e| e.to_string()))
+            .collect();
+        let result: Vec<_> = BinSliceRecords::new(&data)
+            .with_mode(ParseMode::Lenient)
+            .map(|r| r.map(|t| t.to_transaction()).map_err(|e| e.to_string()))
+            .collect();
+        assert_eq!(result, expected);
+        assert_eq!(result.len(), 4);
+        assert_eq!(result[2].as_ref().unwrap().tx_id, 2);
+
+        let limits = ParseLimits {
+            max_description_bytes: Some(4),
+            ..Default::default()
+        };
+        let data = write_v2();
+        let result: Vec<_> = BinSliceRecords::new(&data)
+            .with_limits(limits)
+            .map(|r| r.map(|t| t.tx_id()).map_err(|e| e.to_string()))
+            .collect();
+        assert_eq!(
+            result,
+            [Err(
+                "Превышена максимальная длина описания 4 байт в записи 0 на смещении 76"
+                    .to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_success_open() {
+        let path = std::env::temp_dir().join(format!("bin_mmap_{}.bin", std::process::id()));
+        let mut data = Vec::new();
+        BinParser::write_to(&mut data, &get_transactions()).unwrap();
+        std::fs::write(&path, &data).unwrap();
+
+        // SAFETY: временный файл теста не изменяется до удаления отображения
+        let mmap = unsafe { BinMmap::open(&path) }.unwrap();
+        assert_eq!(mmap.as_bytes(), data);
+        let records = mmap.records();
+        assert_eq!(
+            records.map(|r| r.unwrap().amount()).sum::<u64>(),
+            get_transactions().iter().map(|t| t.amount()).sum::<u64>()
+        );
+
+        drop(mmap);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_error_records() {
+        let data = write_v2();
+        let trailer = data.len() - 16;
+
+        let mut corrupted = data.clone();
+        corrupted[BinHeader::SIZE + 40] ^= 1; // AMOUNT первой записи
+        let result: Vec<_> = BinSliceRecords::new(&corrupted)
+            .map(|r| r.map(|t| t.tx_id()).map_err(|e| e.to_string()))
+            .collect();
+        assert_eq!(
+            result,
+            [Err(
+                "Некорректная контрольная сумма записи 0 на смещении 22".to_string()
+            )]
+        );
+
+        let result = BinSliceRecords::new(&data[..trailer])
+            .last()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            format!("Отсутствует завершающий блок файла на смещении {trailer}")
+        );
+    }
+}
@@ -1,4 +1,12 @@
+/// Модуль разбора заголовка, записей и завершающего блока из байтов
+pub(crate) mod decode;
 /// Модуль ошибок
 pub mod error;
+/// Модуль заголовка файла
+pub mod header;
+/// Модуль индекса для произвольного доступа к записям
+pub mod index;
+/// Модуль чтения без копирования из отображённого в память файла
+pub mod mmap;
 /// Модуль парсера
 pub mod parser;
@@ -1,194 +1,764 @@
-use std::io::{Read, Write};
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, Read, Write},
+};
+
+use crc32fast::Hasher;
 
 use crate::{
-    Field, Parser, Status, Transaction, TxType,
-    errors::WriteError,
-    parsers::{bin::error::BinError, utils::description_trim},
+    Format, ParseLimits, ParseMode, ParseOptions, Parser, Status, Transaction, TransactionWriter,
+    TxType,
+    errors::{ParserError, WriteError},
+    parsers::bin::{
+        decode::{
+            CHECKSUM_SIZE, RECORD_HEAD_SIZE, RecordHead, TRAILER_SIZE, check_trailer,
+            decode_header, is_recoverable, needs_resync,
+        },
+        error::BinError,
+        header::{self, BinHeader, BinVersion, FILE_MAGIC, TRAILER_MAGIC},
+    },
 };
 
 /// Парсер для bin формата
-pub struct BinParser;
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct BinParser {
+    options: ParseOptions,
+    version: BinVersion,
+}
 
-const MAGIC: &[u8; 4] = b"YPBN";
-const RECORD_SIZE_WITHOUT_DESC: u32 = 8 + 1 + 8 + 8 + 8 + 8 + 1 + 4;
+impl BinParser {
+    /// Создание парсера с настройками по умолчанию
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-impl Parser for BinParser {
-    type Error = BinError;
+    /// Установка режима разбора
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.options.mode = mode;
+        self
+    }
 
-    fn from_read<R: Read>(reader: &mut R) -> Result<Vec<Transaction>, BinError> {
-        let mut data: Vec<u8> = Vec::new();
-        reader.read_to_end(&mut data).map_err(|_| BinError::Read)?;
-        let length = data.len();
-
-        let mut transactions: Vec<Transaction> = Vec::new();
-
-        let mut offset = 0;
-        let mut record_index = 0;
-
-        while offset < length {
-            let mut take = |n: usize| -> Result<&[u8], BinError> {
-                let start = offset;
-                let end = start + n;
-                if end > length {
-                    return Err(BinError::InvalidLength {
-                        index: record_index,
-                    });
+    /// Установка ограничений на размер входных данных
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.options.limits = limits;
+        self
+    }
+
+    /// Установка версии формата для записи, по умолчанию v1.
+    /// Чтение поддерживает обе версии
+    pub fn with_version(mut self, version: BinVersion) -> Self {
+        self.version = version;
+        self
+    }
+}
+
+pub(crate) const MAGIC: &[u8; 4] = b"YPBN";
+pub(crate) const RECORD_SIZE_WITHOUT_DESC: u32 = 8 + 1 + 8 + 8 + 8 + 8 + 1 + 4;
+
+/// Потоковое чтение записей bin формата.
+/// Версия формата определяется по заголовку файла, для v2 проверяются
+/// контрольные суммы записей и завершающий блок.
+/// В режиме [`ParseMode::Lenient`] после повреждённой записи
+/// чтение продолжается со следующего найденного MAGIC
+pub struct BinRecords<R> {
+    reader: R,
+    mode: ParseMode,
+    limits: ParseLimits,
+    version: BinVersion,
+    header: Option<BinHeader>,
+    /// Контрольная сумма всех байтов, прочитанных из потока
+    hasher: Hasher,
+    started: bool,
+    trailer_read: bool,
+    /// Чтение части файла, которая не заканчивается завершающим блоком
+    partial: bool,
+    record_index: usize,
+    offset: u64,
+    /// Смещение начала текущей записи
+    record_offset: u64,
+    /// Прочитанные байты текущей записи
+    record: Vec<u8>,
+    /// Байты, возвращённые в поток для повторного поиска MAGIC
+    pending: VecDeque<u8>,
+    resync: bool,
+    count: usize,
+    finished: bool,
+}
+
+impl<R: BufRead> BinRecords<R> {
+    /// Создание итератора по записям
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            mode: ParseMode::Strict,
+            limits: ParseLimits::default(),
+            version: BinVersion::V1,
+            header: None,
+            hasher: Hasher::new(),
+            started: false,
+            trailer_read: false,
+            partial: false,
+            record_index: 0,
+            offset: 0,
+            record_offset: 0,
+            record: Vec::new(),
+            pending: VecDeque::new(),
+            resync: false,
+            count: 0,
+            finished: false,
+        }
+    }
+
+    /// Установка режима разбора
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Установка ограничений на размер входных данных
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Чтение части файла, начиная с записи `record_index` на смещении `offset`.
+    /// Поток должен быть установлен на начало этой записи
+    pub(crate) fn with_position(
+        mut self,
+        version: BinVersion,
+        offset: u64,
+        record_index: usize,
+    ) -> Self {
+        self.version = version;
+        self.offset = offset;
+        self.record_index = record_index;
+        self.started = true;
+        self.partial = true;
+        self
+    }
+
+    /// Заголовок файла. Для файлов v1 отсутствует
+    pub fn header(&self) -> Option<&BinHeader> {
+        self.header.as_ref()
+    }
+
+    /// Проверка, что чтение `n` байт не превысит [`ParseLimits::max_input_bytes`]
+    fn check_input(&self, n: usize) -> Result<(), BinError> {
+        match self.limits.max_input_bytes {
+            Some(limit) if self.offset.saturating_add(n as u64) > limit => {
+                Err(BinError::InputTooLarge {
+                    offset: self.offset,
+                    limit,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn map_read_error(&self, error: io::Error) -> BinError {
+        if error.kind() == io::ErrorKind::UnexpectedEof {
+            BinError::InvalidLength {
+                index: self.record_index,
+                offset: self.offset,
+            }
+        } else {
+            BinError::Read {
+                offset: self.offset,
+                source: error,
+            }
+        }
+    }
+
+    fn read_raw(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        let from_pending = buf.len().min(self.pending.len());
+        for (b, p) in buf.iter_mut().zip(self.pending.drain(..from_pending)) {
+            *b = p;
+        }
+        self.reader.read_exact(&mut buf[from_pending..])?;
+        self.hasher.update(&buf[from_pending..]);
+        self.record.extend_from_slice(buf);
+        Ok(())
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], BinError> {
+        self.check_input(N)?;
+        let mut value = [0; N];
+        self.read_raw(&mut value)
+            .map_err(|e| self.map_read_error(e))?;
+        self.offset += N as u64;
+        Ok(value)
+    }
+
+    /// Чтение до `n` байт в текущую запись. Меньше `n` байт читается,
+    /// только если поток закончился
+    fn take_upto(&mut self, n: usize) -> Result<usize, BinError> {
+        self.check_input(n)?;
+        let from_pending = n.min(self.pending.len());
+        let start = self.record.len();
+        self.record.extend(self.pending.drain(..from_pending));
+        let read = (&mut self.reader)
+            .take((n - from_pending) as u64)
+            .read_to_end(&mut self.record)
+            .map_err(|e| self.map_read_error(e))?;
+        self.hasher.update(&self.record[start + from_pending..]);
+        self.offset += (from_pending + read) as u64;
+        Ok(from_pending + read)
+    }
+
+    /// Пропуск `n` байт без сохранения в памяти
+    fn skip(&mut self, n: usize) -> Result<(), BinError> {
+        self.check_input(n)?;
+        let from_pending = n.min(self.pending.len());
+        self.pending.drain(..from_pending);
+        let skipped = io::copy(
+            &mut (&mut self.reader).take((n - from_pending) as u64),
+            &mut ChecksumSink(&mut self.hasher),
+        )
+        .map_err(|e| self.map_read_error(e))?;
+        if skipped as usize + from_pending != n {
+            return Err(BinError::InvalidLength {
+                index: self.record_index,
+                offset: self.offset,
+            });
+        }
+        self.offset += n as u64;
+        Ok(())
+    }
+
+    fn is_eof(&mut self) -> Result<bool, BinError> {
+        let offset = self.offset;
+        Ok(self.pending.is_empty()
+            && self
+                .reader
+                .fill_buf()
+                .map_err(|source| BinError::Read { offset, source })?
+                .is_empty())
+    }
+
+    /// Возврат в поток всех байтов повреждённой записи, кроме первого,
+    /// чтобы следующий MAGIC искался сразу после начала этой записи
+    fn rewind_record(&mut self) {
+        for &b in self.record.iter().skip(1).rev() {
+            self.pending.push_front(b);
+        }
+        if !self.record.is_empty() {
+            self.offset = self.record_offset + 1;
+        }
+        self.record.clear();
+    }
+
+    /// Поиск следующего MAGIC. Возвращает `false`, если поток закончился
+    fn resync(&mut self) -> Result<bool, BinError> {
+        let mut window = [0; 4];
+        let mut filled = 0;
+        loop {
+            if self.is_eof()? {
+                return Ok(false);
+            }
+            let [byte] = self.take()?;
+            self.record.clear();
+            window = [window[1], window[2], window[3], byte];
+            filled += 1;
+            let is_trailer = self.version == BinVersion::V2 && &window == TRAILER_MAGIC;
+            if filled >= MAGIC.len() && (&window == MAGIC || is_trailer) {
+                for &b in MAGIC.iter().rev() {
+                    self.pending.push_front(b);
                 }
-                let value = &data[start..end];
-                offset += n;
-                Ok(value)
-            };
+                self.offset -= MAGIC.len() as u64;
+                return Ok(true);
+            }
+        }
+    }
 
-            let get_value_u32 = |value: &[u8]| -> Result<u32, BinError> {
-                Ok(u32::from_be_bytes(
-                    value.try_into().map_err(|_| BinError::Unknown)?,
-                ))
-            };
-            let get_value_u64 = |value: &[u8]| -> Result<u64, BinError> {
-                Ok(u64::from_be_bytes(
-                    value.try_into().map_err(|_| BinError::Unknown)?,
-                ))
-            };
-            let get_value_i32 = |value: &[u8]| -> Result<i32, BinError> {
-                Ok(i32::from_be_bytes(
-                    value.try_into().map_err(|_| BinError::Unknown)?,
-                ))
-            };
-            let get_value_i64 = |value: &[u8]| -> Result<i64, BinError> {
-                Ok(i64::from_be_bytes(
-                    value.try_into().map_err(|_| BinError::Unknown)?,
-                ))
-            };
+    /// Чтение заголовка bin v2. Для файлов v1 прочитанные байты возвращаются в поток
+    fn read_header(&mut self) -> Result<(), BinError> {
+        self.record.clear();
+        self.take_upto(FILE_MAGIC.len())?;
+        if self.record[..] == FILE_MAGIC[..] {
+            self.take_upto(BinHeader::SIZE - FILE_MAGIC.len())?;
+        }
+        let Some(header) = decode_header(&self.record)? else {
+            for &b in self.record.iter().rev() {
+                self.pending.push_front(b);
+            }
+            self.offset = 0;
+            self.record.clear();
+            return Ok(());
+        };
+        self.record.clear();
+
+        self.version = BinVersion::V2;
+        self.header = Some(header);
+        Ok(())
+    }
 
-            let magic = take(4)?;
-            if magic != MAGIC {
-                return Err(BinError::InvalidMagic {
-                    index: record_index,
-                });
+    /// Чтение завершающего блока v2 после его MAGIC и проверка целостности файла.
+    /// `checksum` - контрольная сумма всех байтов до завершающего блока
+    fn read_trailer(&mut self, checksum: u32) -> Result<Option<Transaction>, BinError> {
+        self.trailer_read = true;
+        self.take_upto(TRAILER_SIZE - TRAILER_MAGIC.len())?;
+        check_trailer(
+            &self.record,
+            self.record_offset,
+            checksum,
+            self.header.as_ref(),
+            self.record_index,
+        )?;
+
+        if !self.is_eof()? {
+            return Err(BinError::TrailingData {
+                offset: self.offset,
+            });
+        }
+        Ok(None)
+    }
+
+    /// Окончание потока. Файл v2 обязан заканчиваться завершающим блоком
+    fn end_of_input(&self) -> Result<Option<Transaction>, BinError> {
+        if self.version == BinVersion::V2 && !self.trailer_read && !self.partial {
+            return Err(BinError::MissingTrailer {
+                offset: self.offset,
+            });
+        }
+        Ok(None)
+    }
+
+    fn read_record(&mut self) -> Result<Option<Transaction>, BinError> {
+        if !self.started {
+            self.started = true;
+            self.read_header()?;
+        }
+
+        if self.resync {
+            self.resync = false;
+            if !self.resync()? {
+                return self.end_of_input();
             }
-            let _record_size =
-                get_value_u32(take(4)?).map_err(|_| BinError::InvalidRecordSize {
-                    index: record_index,
-                })?;
+        }
 
-            let tx_id = get_value_u64(take(8)?).map_err(|_| BinError::InvalidField {
-                index: record_index,
-                field: Field::TxId,
-            })?;
-            let tx_type: TxType = match take(1)?[0] {
-                0 => TxType::Deposit,
-                1 => TxType::Transfer,
-                2 => TxType::Withdrawal,
-                _ => Err(BinError::InvalidField {
-                    index: record_index,
-                    field: Field::TxType,
-                })?,
-            };
-            let from_user_id = get_value_u64(take(8)?).map_err(|_| BinError::InvalidField {
-                index: record_index,
-                field: Field::FromUserId,
-            })?;
-            let to_user_id = get_value_u64(take(8)?).map_err(|_| BinError::InvalidField {
-                index: record_index,
-                field: Field::ToUserId,
-            })?;
-            let amount = get_value_u64(take(8)?).map_err(|_| BinError::InvalidField {
-                index: record_index,
-                field: Field::Amount,
-            })?;
-            let timestamp = get_value_i64(take(8)?).map_err(|_| BinError::InvalidField {
+        if self.trailer_read || self.is_eof()? {
+            return self.end_of_input();
+        }
+
+        let record_index = self.record_index;
+        self.record_offset = self.offset;
+        self.record.clear();
+
+        let checksum = self.hasher.clone().finalize();
+        let magic = self.take::<4>()?;
+        if self.version == BinVersion::V2 && &magic == TRAILER_MAGIC {
+            return self.read_trailer(checksum);
+        }
+        // поля читаются, пока хватает данных, чтобы ошибка указывала на обрезанное поле
+        if &magic == MAGIC {
+            self.take_upto(RECORD_HEAD_SIZE - MAGIC.len())?;
+        }
+        let head = RecordHead::decode(&self.record, record_index, self.record_offset)?;
+
+        let description_offset = self.offset;
+        if let Some(limit) = self.limits.max_description_bytes
+            && head.desc_len > limit
+        {
+            self.skip(head.desc_len)?;
+            if self.version == BinVersion::V2 {
+                self.skip(CHECKSUM_SIZE)?;
+            }
+            self.record_index += 1;
+            return Err(BinError::DescriptionTooLong {
                 index: record_index,
-                field: Field::Timestamp,
-            })?;
-            let status: Status = match take(1)?[0] {
-                0 => Status::Success,
-                1 => Status::Failure,
-                2 => Status::Pending,
-                _ => Err(BinError::InvalidField {
-                    index: record_index,
-                    field: Field::Status,
-                })?,
-            };
-            let desc_len = get_value_i32(take(4)?).map_err(|_| BinError::InvalidDescLen {
+                offset: description_offset,
+                limit,
+            });
+        }
+        if self.take_upto(head.desc_len)? != head.desc_len {
+            return Err(BinError::InvalidLength {
                 index: record_index,
-            })?;
-            let description = str::from_utf8(take(desc_len as usize)?)
-                .map_err(|_| BinError::InvalidField {
-                    index: record_index,
-                    field: Field::Description,
-                })?
-                .to_string();
-            let description =
-                description_trim(&description).map_err(|_| BinError::InvalidField {
-                    index: record_index,
-                    field: Field::Description,
-                })?;
-
-            record_index += 1;
-            transactions.push(Transaction {
-                tx_id,
-                tx_type,
-                from_user_id,
-                to_user_id,
-                amount,
-                timestamp,
-                status,
-                description,
+                offset: description_offset,
             });
         }
+        let record_len = self.record.len();
+        let checksum = match self.version {
+            BinVersion::V1 => None,
+            BinVersion::V2 => Some(u32::from_be_bytes(self.take()?)),
+        };
+
+        // запись прочитана целиком, поэтому после ошибки в значениях полей
+        // чтение можно продолжить со следующей записи
+        self.record_index += 1;
+
+        let t = head.finish(&self.record[..record_len], checksum)?;
+        Ok(Some(Transaction::from(t)))
+    }
+}
+
+/// Файл начинается с заголовка v2 или с записи v1
+pub(crate) fn sniff(data: &[u8]) -> bool {
+    data.starts_with(MAGIC) || data.starts_with(FILE_MAGIC)
+}
+
+impl<R: BufRead> Iterator for BinRecords<R> {
+    type Item = Result<Transaction, BinError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let mut result = self.read_record().transpose();
+        if result.is_some()
+            && let Some(limit) = self.limits.max_records
+            && self.count >= limit
+        {
+            result = Some(Err(BinError::TooManyRecords { limit }));
+        }
+        self.count += 1;
+        match &result {
+            None => self.finished = true,
+            Some(Ok(_)) => {}
+            Some(Err(_)) if self.mode == ParseMode::Strict => self.finished = true,
+            Some(Err(e)) if is_recoverable(e) => {}
+            Some(Err(e)) if needs_resync(e) => {
+                self.record_index += 1;
+                self.rewind_record();
+                self.resync = true;
+            }
+            Some(Err(_)) => self.finished = true,
+        }
+        result
+    }
+}
 
-        Ok(transactions)
+impl Parser for BinParser {
+    type Error = BinError;
+
+    fn records_with_options<R: BufRead>(
+        reader: R,
+        options: ParseOptions,
+    ) -> impl Iterator<Item = Result<Transaction, BinError>> {
+        BinRecords::new(reader)
+            .with_mode(options.mode)
+            .with_limits(options.limits)
+    }
+
+    fn writer<W: Write>(writer: W) -> impl TransactionWriter {
+        BinWriter::new(writer)
     }
 
     fn write_to<W: Write>(writer: &mut W, transactions: &[Transaction]) -> Result<(), WriteError> {
+        let mut writer = BinWriter::new(writer).with_record_count(transactions.len() as u64);
+        for t in transactions {
+            writer.write(t)?;
+        }
+        writer.finish()
+    }
+}
+
+impl Format for BinParser {
+    fn parse_options(&self) -> ParseOptions {
+        self.options
+    }
+
+    fn with_parse_options(&self, options: ParseOptions) -> Box<dyn Format> {
+        Box::new(Self { options, ..*self })
+    }
+
+    fn read_records<'a>(
+        &self,
+        reader: &'a mut dyn BufRead,
+    ) -> Box<dyn Iterator<Item = Result<Transaction, ParserError>> + 'a> {
+        let records = BinRecords::new(reader)
+            .with_mode(self.options.mode)
+            .with_limits(self.options.limits);
+        Box::new(records.map(|r| Ok(r?)))
+    }
+
+    fn create_writer<'a>(&self, writer: &'a mut dyn Write) -> Box<dyn TransactionWriter + 'a> {
+        Box::new(BinWriter::new(writer).with_version(self.version))
+    }
+
+    fn write_transactions(
+        &self,
+        writer: &mut dyn Write,
+        transactions: &[Transaction],
+    ) -> Result<(), ParserError> {
+        let mut writer = BinWriter::new(writer)
+            .with_version(self.version)
+            .with_record_count(transactions.len() as u64);
         for t in transactions {
-            let mut data: Vec<u8> = Vec::new();
-            data.extend_from_slice(MAGIC);
-
-            let description = format!("\"{}\"", t.description);
-            let description = description.as_bytes();
-            let desc_len = description.len() as u32;
-
-            data.extend_from_slice(
-                &((RECORD_SIZE_WITHOUT_DESC + desc_len).to_be_bytes() as [u8; 4]),
-            );
-            data.extend_from_slice(&(t.tx_id.to_be_bytes() as [u8; 8]));
-            data.extend_from_slice(&[match t.tx_type {
-                TxType::Deposit => 0,
-                TxType::Transfer => 1,
-                TxType::Withdrawal => 2,
-            }]);
-            data.extend_from_slice(&(t.from_user_id.to_be_bytes() as [u8; 8]));
-            data.extend_from_slice(&(t.to_user_id.to_be_bytes() as [u8; 8]));
-            data.extend_from_slice(&(t.amount.to_be_bytes() as [u8; 8]));
-            data.extend_from_slice(&(t.timestamp.to_be_bytes() as [u8; 8]));
-            data.extend_from_slice(&[match t.status {
-                Status::Success => 0,
-                Status::Failure => 1,
-                Status::Pending => 2,
-            }]);
-            data.extend_from_slice(&(desc_len.to_be_bytes() as [u8; 4]));
-            data.extend_from_slice(description);
-
-            writer.write_all(&data).map_err(|_| WriteError::Write)?;
-        }
-        writer.flush().map_err(|_| WriteError::Write)?;
+            writer.write(t)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+/// Приёмник пропускаемых байтов, который только обновляет контрольную сумму
+struct ChecksumSink<'a>(&'a mut Hasher);
+
+impl Write for ChecksumSink<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
         Ok(())
     }
 }
 
+/// Потоковая запись транзакций в bin формате.
+/// По умолчанию записывается формат v1, формат v2 включается через
+/// [`BinWriter::with_version`], его завершающий блок записывается в [`TransactionWriter::finish`]
+pub struct BinWriter<W> {
+    writer: W,
+    version: BinVersion,
+    record_count: Option<u64>,
+    created_at: Option<i64>,
+    /// Контрольная сумма всех записанных байтов
+    hasher: Hasher,
+    count: u64,
+    header_written: bool,
+}
+
+impl<W: Write> BinWriter<W> {
+    /// Создание писателя
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            version: BinVersion::default(),
+            record_count: None,
+            created_at: None,
+            hasher: Hasher::new(),
+            count: 0,
+            header_written: false,
+        }
+    }
+
+    /// Установка версии формата, по умолчанию v1
+    pub fn with_version(mut self, version: BinVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Количество записей для заголовка v2, если оно известно заранее
+    pub fn with_record_count(mut self, record_count: u64) -> Self {
+        self.record_count = Some(record_count);
+        self
+    }
+
+    /// Время создания файла для заголовка v2. По умолчанию текущее время
+    pub fn with_created_at(mut self, created_at: i64) -> Self {
+        self.created_at = Some(created_at);
+        self
+    }
+
+    fn write_data(&mut self, data: &[u8]) -> Result<(), WriteError> {
+        self.hasher.update(data);
+        self.writer.write_all(data).map_err(WriteError::Write)
+    }
+
+    fn write_header(&mut self) -> Result<(), WriteError> {
+        if self.header_written {
+            return Ok(());
+        }
+        self.header_written = true;
+        if self.version == BinVersion::V1 {
+            return Ok(());
+        }
+        let header = BinHeader {
+            version: self.version,
+            record_count: self.record_count,
+            created_at: self.created_at.unwrap_or_else(header::now_millis),
+        };
+        self.write_data(&header.to_bytes())
+    }
+
+    /// Получение исходного потока
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> TransactionWriter for BinWriter<W> {
+    fn write(&mut self, t: &Transaction) -> Result<(), WriteError> {
+        let mut data: Vec<u8> = Vec::new();
+        data.extend_from_slice(MAGIC);
+
+        let description = format!("\"{}\"", t.description);
+        let description = description.as_bytes();
+        let desc_len = description.len() as u32;
+
+        data.extend_from_slice(&((RECORD_SIZE_WITHOUT_DESC + desc_len).to_be_bytes() as [u8; 4]));
+        data.extend_from_slice(&(t.tx_id.to_be_bytes() as [u8; 8]));
+        data.extend_from_slice(&[match t.tx_type {
+            TxType::Deposit => 0,
+            TxType::Transfer => 1,
+            TxType::Withdrawal => 2,
+        }]);
+        data.extend_from_slice(&(t.from_user_id.to_be_bytes() as [u8; 8]));
+        data.extend_from_slice(&(t.to_user_id.to_be_bytes() as [u8; 8]));
+        data.extend_from_slice(&(t.amount.to_be_bytes() as [u8; 8]));
+        data.extend_from_slice(&(t.timestamp.to_be_bytes() as [u8; 8]));
+        data.extend_from_slice(&[match t.status {
+            Status::Success => 0,
+            Status::Failure => 1,
+            Status::Pending => 2,
+        }]);
+        data.extend_from_slice(&(desc_len.to_be_bytes() as [u8; 4]));
+        data.extend_from_slice(description);
+        if self.version == BinVersion::V2 {
+            data.extend_from_slice(&crc32fast::hash(&data).to_be_bytes());
+        }
+
+        self.write_header()?;
+        self.count += 1;
+        self.write_data(&data)
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        self.write_header()?;
+        if self.version == BinVersion::V2 {
+            let checksum = self.hasher.clone().finalize();
+            let mut data = Vec::new();
+            data.extend_from_slice(TRAILER_MAGIC);
+            data.extend_from_slice(&self.count.to_be_bytes());
+            data.extend_from_slice(&checksum.to_be_bytes());
+            self.write_data(&data)?;
+        }
+        self.writer.flush().map_err(WriteError::Write)
+    }
+}
+
 #[cfg(test)]
 mod tests_from_read {
-    use std::io::Cursor;
+    use std::io::{BufReader, Cursor};
 
     use super::*;
 
+    fn write_v1(writer: &mut Vec<u8>, transactions: &[Transaction]) -> Result<(), WriteError> {
+        BinParser::write_to(writer, transactions)
+    }
+
+    fn write_v2(transactions: &[Transaction]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut writer = BinWriter::new(&mut data)
+            .with_version(BinVersion::V2)
+            .with_record_count(transactions.len() as u64);
+        for t in transactions {
+            writer.write(t).unwrap();
+        }
+        writer.finish().unwrap();
+        data
+    }
+
+    fn read_errors(data: Vec<u8>, mode: ParseMode) -> Vec<Result<u64, String>> {
+        BinParser::records_with_mode(BufReader::new(Cursor::new(data)), mode)
+            .map(|r| r.map(|t| t.tx_id).map_err(|e| e.to_string()))
+            .collect()
+    }
+
+    fn get_transactions() -> Vec<Transaction> {
+        (0..3)
+            .map(|tx_id| Transaction {
+                tx_id,
+                amount: 100,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_success_read_v2() {
+        let data = write_v2(&get_transactions());
+        let mut records = BinRecords::new(BufReader::new(Cursor::new(data)));
+        let result: Vec<Transaction> = records.by_ref().map(|r| r.unwrap()).collect();
+        assert_eq!(result, get_transactions());
+        let header = records.header().unwrap();
+        assert_eq!(header.version, BinVersion::V2);
+        assert_eq!(header.record_count, Some(3));
+
+        let data = write_v2(&[]);
+        assert!(
+            BinParser::from_read(&mut Cursor::new(data))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_error_missing_trailer() {
+        // обрезка файла ровно по границе записи
+        let mut data = write_v2(&get_transactions());
+        let record_len = (data.len() - BinHeader::SIZE - 16) / 3;
+        data.truncate(BinHeader::SIZE + record_len * 2);
+        assert_eq!(
+            read_errors(data, ParseMode::Strict),
+            [
+                Ok(0),
+                Ok(1),
+                Err("Отсутствует завершающий блок файла на смещении 142".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_invalid_checksum() {
+        let mut data = write_v2(&get_transactions());
+        let record_len = (data.len() - BinHeader::SIZE - 16) / 3;
+        data[BinHeader::SIZE + record_len + 40] ^= 1; // AMOUNT второй записи
+        assert_eq!(
+            read_errors(data, ParseMode::Lenient),
+            [
+                Ok(0),
+                Err("Некорректная контрольная сумма записи 1 на смещении 82".to_string()),
+                Ok(2),
+                Err("Некорректная контрольная сумма файла на смещении 202".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_trailer() {
+        let mut data = write_v2(&get_transactions());
+        let len = data.len();
+        data[len - 5] = 2; // RECORD_COUNT в завершающем блоке
+        let result = BinParser::from_read(&mut Cursor::new(data)).unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Количество записей 3 не совпадает с заявленным 2"
+        );
+
+        let mut data = write_v2(&get_transactions());
+        data.push(0);
+        let result = BinParser::from_read(&mut Cursor::new(data)).unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Данные после завершающего блока на смещении 218"
+        );
+    }
+
+    #[test]
+    fn test_error_header() {
+        let mut data = write_v2(&get_transactions());
+        data[5] = 3; // VERSION
+        let result = BinParser::from_read(&mut Cursor::new(data)).unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Неподдерживаемая версия формата 3 на смещении 4"
+        );
+
+        let result = BinParser::from_read(&mut Cursor::new(FILE_MAGIC.to_vec())).unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Некорректный заголовок файла на смещении 4"
+        );
+    }
+
     #[test]
     fn test_success_from_read() {
         let mut data: Vec<u8> = Vec::new();
 
         data.extend_from_slice(&[89, 80, 66, 78]); // MAGIC
-        data.extend_from_slice(&[0, 0, 0, 54]); // RECORD_SIZE
+        data.extend_from_slice(&[0, 0, 0, 56]); // RECORD_SIZE
         data.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]); // ID
         data.extend_from_slice(&[0]); // TX_TYPE
         data.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0]); // FROM_USER_ID
@@ -217,6 +787,59 @@ mod tests_from_read {
         );
     }
 
+    #[test]
+    fn test_success_records() {
+        let transactions: Vec<Transaction> = (0..3)
+            .map(|tx_id| Transaction {
+                tx_id,
+                description: format!("record {tx_id}"),
+                ..Default::default()
+            })
+            .collect();
+        let mut data = Vec::new();
+        write_v1(&mut data, &transactions).unwrap();
+        data.extend_from_slice(MAGIC);
+
+        let mut records = BinParser::records(BufReader::new(Cursor::new(data)));
+        for transaction in &transactions {
+            assert_eq!(&records.next().unwrap().unwrap(), transaction);
+        }
+        assert_eq!(
+            records.next().unwrap().unwrap_err().to_string(),
+            "Неожиданное завершение записи 3 на смещении 196"
+        );
+        assert!(records.next().is_none());
+    }
+
+    #[test]
+    fn test_success_records_lenient() {
+        let transactions: Vec<Transaction> = (0..3)
+            .map(|tx_id| Transaction {
+                tx_id,
+                ..Default::default()
+            })
+            .collect();
+        let mut data = Vec::new();
+        write_v1(&mut data, &transactions).unwrap();
+        let record_len = data.len() / 3;
+        data[record_len + 16] = 9; // TX_TYPE второй записи
+        data.extend_from_slice(MAGIC);
+
+        let result: Vec<_> =
+            BinParser::records_with_mode(BufReader::new(Cursor::new(data)), ParseMode::Lenient)
+                .map(|r| r.map(|t| t.tx_id).map_err(|e| e.to_string()))
+                .collect();
+        assert_eq!(
+            result,
+            [
+                Ok(0),
+                Err("Ошибка парсинга поля TX_TYPE в записи 1 на смещении 72".to_string()),
+                Ok(2),
+                Err("Неожиданное завершение записи 3 на смещении 172".to_string()),
+            ]
+        );
+    }
+
     #[test]
     fn test_error_invalid_length() {
         let mut data: Vec<u8> = Vec::new();
@@ -225,7 +848,149 @@ mod tests_from_read {
         let mut cursor = Cursor::new(data);
 
         let result = BinParser::from_read(&mut cursor).unwrap_err();
-        assert_eq!(result.to_string(), "Неожиданное завершение записи 0");
+        assert_eq!(
+            result.to_string(),
+            "Неожиданное завершение записи 0 на смещении 0"
+        );
+    }
+
+    #[test]
+    fn test_error_utf8() {
+        let mut data = Vec::new();
+        write_v1(&mut data, &[Transaction::default()]).unwrap();
+        data.pop();
+        data.extend_from_slice(&[0xff, b'"']);
+        data[7] = 49; // RECORD_SIZE
+        data[53] = 3; // DESC_LEN
+
+        let result = BinParser::from_read(&mut Cursor::new(data)).unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Некорректная кодировка UTF-8 в описании записи 0 на смещении 55"
+        );
+    }
+
+    #[test]
+    fn test_error_invalid_record_size() {
+        let mut data = Vec::new();
+        write_v1(&mut data, &[Transaction::default()]).unwrap();
+        data[7] = 100; // RECORD_SIZE
+
+        let result = BinParser::from_read(&mut Cursor::new(data)).unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Некорректный RECORD_SIZE в записи 0 на смещении 4"
+        );
+    }
+
+    #[test]
+    fn test_success_records_resync() {
+        let transactions: Vec<Transaction> = (0..4)
+            .map(|tx_id| Transaction {
+                tx_id,
+                ..Default::default()
+            })
+            .collect();
+        let mut records = Vec::new();
+        for transaction in &transactions {
+            let mut record = Vec::new();
+            write_v1(&mut record, std::slice::from_ref(transaction)).unwrap();
+            records.push(record);
+        }
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&records[0]);
+        data.extend_from_slice(b"garbageYP"); // мусор между записями
+        data.extend_from_slice(&records[1]);
+        records[2][7] = 200; // RECORD_SIZE
+        data.extend_from_slice(&records[2]);
+        data.extend_from_slice(&records[3]);
+        data.extend_from_slice(&MAGIC[..2]); // обрезанная запись
+
+        let result: Vec<_> =
+            BinParser::records_with_mode(BufReader::new(Cursor::new(data)), ParseMode::Lenient)
+                .map(|r| r.map(|t| t.tx_id).map_err(|e| e.to_string()))
+                .collect();
+        assert_eq!(
+            result,
+            [
+                Ok(0),
+                Err("Некорректный MAGIC в записи 1 на смещении 56".to_string()),
+                Ok(1),
+                Err("Некорректный RECORD_SIZE в записи 3 на смещении 125".to_string()),
+                Ok(3),
+                Err("Неожиданное завершение записи 5 на смещении 233".to_string()),
+            ]
+        );
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&records[0]);
+        data.extend_from_slice(b"garbage");
+        let result = BinParser::from_read(&mut Cursor::new(data)).unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Некорректный MAGIC в записи 1 на смещении 56"
+        );
+    }
+
+    #[test]
+    fn test_error_limits() {
+        let mut data = Vec::new();
+        for (tx_id, description) in [(0, "a".repeat(20)), (1, String::new()), (2, String::new())] {
+            let transaction = Transaction {
+                tx_id,
+                description,
+                ..Default::default()
+            };
+            write_v1(&mut data, &[transaction]).unwrap();
+        }
+        let options = ParseOptions {
+            mode: ParseMode::Lenient,
+            limits: ParseLimits {
+                max_description_bytes: Some(10),
+                max_records: Some(2),
+                ..Default::default()
+            },
+        };
+        let result: Vec<_> =
+            BinParser::records_with_options(BufReader::new(Cursor::new(data)), options)
+                .map(|r| r.map(|t| t.tx_id).map_err(|e| e.to_string()))
+                .collect();
+        assert_eq!(
+            result,
+            [
+                Err(
+                    "Превышена максимальная длина описания 10 байт в записи 0 на смещении 54"
+                        .to_string()
+                ),
+                Ok(1),
+                Err("Превышено максимальное количество записей 2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_input_too_large() {
+        let mut data = Vec::new();
+        write_v1(&mut data, &[Transaction::default()]).unwrap();
+        // огромная длина описания не должна приводить к выделению памяти
+        data[4..8].copy_from_slice(&(i32::MAX as u32).to_be_bytes());
+        data[50..54].copy_from_slice(&(i32::MAX - 46).to_be_bytes());
+        let options = ParseOptions {
+            limits: ParseLimits {
+                max_input_bytes: Some(1024),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = BinParser::records_with_options(BufReader::new(Cursor::new(data)), options)
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Превышен максимальный размер входных данных 1024 байт на смещении 54"
+        );
     }
 
     #[test]
@@ -236,7 +1001,10 @@ mod tests_from_read {
         let mut cursor = Cursor::new(data);
 
         let result = BinParser::from_read(&mut cursor).unwrap_err();
-        assert_eq!(result.to_string(), "Некорректный MAGIC в записи 0");
+        assert_eq!(
+            result.to_string(),
+            "Некорректный MAGIC в записи 0 на смещении 0"
+        );
     }
 }
 
@@ -305,4 +1073,53 @@ mod tests_write_to {
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_success_round_trip_quotes() {
+        let transactions: Vec<Transaction> = ["\"quoted\"", "\"", "", "a \"b\" c"]
+            .into_iter()
+            .map(|description| Transaction {
+                description: description.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        let mut data = Vec::new();
+        BinParser::write_to(&mut data, &transactions).unwrap();
+        assert_eq!(
+            BinParser::from_read(&mut Cursor::new(&data)).unwrap(),
+            transactions
+        );
+    }
+
+    #[test]
+    fn test_success_write_to_v2() {
+        let transactions = [Transaction::default(), Transaction::default()];
+        let mut result = Vec::new();
+        let mut writer = BinWriter::new(&mut result)
+            .with_version(BinVersion::V2)
+            .with_created_at(1633036860000);
+        for t in &transactions {
+            writer.write(t).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let mut record = Vec::new();
+        let mut writer = BinWriter::new(&mut record).with_version(BinVersion::V1);
+        writer.write(&Transaction::default()).unwrap();
+        record.extend_from_slice(&crc32fast::hash(&record).to_be_bytes()); // CRC32
+
+        let mut expected = Vec::new();
+        expected.extend_from_slice(&[89, 80, 66, 70]); // FILE_MAGIC
+        expected.extend_from_slice(&[0, 2]); // VERSION
+        expected.extend_from_slice(&[255; 8]); // RECORD_COUNT неизвестно
+        expected.extend_from_slice(&[0, 0, 1, 124, 56, 148, 250, 96]); // CREATED_AT
+        expected.extend_from_slice(&record);
+        expected.extend_from_slice(&record);
+        let checksum = crc32fast::hash(&expected);
+        expected.extend_from_slice(&[89, 80, 66, 69]); // TRAILER_MAGIC
+        expected.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 2]); // RECORD_COUNT
+        expected.extend_from_slice(&checksum.to_be_bytes()); // CRC32 файла
+
+        assert_eq!(result, expected);
+    }
 }
@@ -0,0 +1,40 @@
+use crate::parsers::csv::tokenizer::{DELIMITER, QUOTE};
+
+/// Настройки csv диалекта
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CsvDialect {
+    /// Разделитель полей
+    pub delimiter: char,
+    /// Символ кавычек
+    pub quote: char,
+    /// Записывать BOM в начало файла. При чтении BOM пропускается всегда
+    pub write_bom: bool,
+    /// Обязательна ли строка заголовка.
+    /// Если заголовка нет, столбцы идут в порядке полей [`crate::Field`]
+    pub header_required: bool,
+    /// Пропускать столбцы заголовка, не совпадающие с именами полей
+    pub ignore_unknown_columns: bool,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        Self {
+            delimiter: DELIMITER,
+            quote: QUOTE,
+            write_bom: false,
+            header_required: true,
+            ignore_unknown_columns: false,
+        }
+    }
+}
+
+impl CsvDialect {
+    /// Диалект Excel с русской локалью: разделитель `;` и BOM
+    pub fn excel_ru() -> Self {
+        Self {
+            delimiter: ';',
+            write_bom: true,
+            ..Default::default()
+        }
+    }
+}
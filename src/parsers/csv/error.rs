@@ -1,3 +1,5 @@
+use std::{io, str::Utf8Error};
+
 use thiserror::Error;
 
 use crate::Field;
@@ -6,11 +8,72 @@ use crate::Field;
 #[derive(Debug, Error)]
 pub enum CsvError {
     /// Ошибка чтения
-    #[error("Ошибка чтения")]
-    Read,
-    /// Ошибка в заголовке
+    #[error("Ошибка чтения в строке {index}: {source}")]
+    Read {
+        /// Индекс строки
+        index: usize,
+        /// Исходная ошибка ввода-вывода
+        #[source]
+        source: io::Error,
+    },
+    /// Строка не является корректной UTF-8 последовательностью
+    #[error("Некорректная кодировка UTF-8 в строке {index}, столбце {column}")]
+    Utf8 {
+        /// Индекс строки
+        index: usize,
+        /// Смещение в строке в байтах
+        column: usize,
+        /// Исходная ошибка декодирования
+        #[source]
+        source: Utf8Error,
+    },
+    /// Кавычка внутри поля без кавычек или символы после закрывающей кавычки
+    #[error("Некорректное использование кавычек в строке {index}, столбце {column}")]
+    InvalidQuote {
+        /// Индекс строки
+        index: usize,
+        /// Смещение в строке в байтах
+        column: usize,
+    },
+    /// Поток закончился внутри поля в кавычках
+    #[error("Незакрытая кавычка в строке {index}, столбце {column}")]
+    UnterminatedQuote {
+        /// Индекс строки
+        index: usize,
+        /// Смещение начала поля в строке в байтах
+        column: usize,
+    },
+    /// Первая строка не является заголовком
     #[error("Некорректный заголовок")]
     Header,
+    /// Неизвестный столбец в заголовке
+    #[error("Неизвестный столбец {name} в заголовке в строке {index}, столбце {column}")]
+    UnknownColumn {
+        /// Индекс строки
+        index: usize,
+        /// Смещение начала поля в строке в байтах
+        column: usize,
+        /// Имя столбца
+        name: String,
+    },
+    /// Повторяющийся столбец в заголовке
+    #[error("Повторяющийся столбец {field} в заголовке в строке {index}, столбце {column}")]
+    DuplicateColumn {
+        /// Индекс строки
+        index: usize,
+        /// Смещение начала поля в строке в байтах
+        column: usize,
+        /// Поле
+        field: Field,
+    },
+    /// В заголовке нет обязательных столбцов
+    #[error("В заголовке в строке {index} отсутствуют столбцы: {}", join_fields(.columns))]
+    MissingColumns {
+        /// Индекс строки
+        index: usize,
+        /// Отсутствующие поля
+        columns: Vec<Field>,
+    },
     /// Некорректное количество элементов в строке
     #[error("Некорректное количество элементов в строке {index}")]
     Length {
@@ -18,11 +81,53 @@ pub enum CsvError {
         index: usize,
     },
     /// Некорректное поле
-    #[error("Ошибка парсинга поля {field} в строке {index}")]
+    #[error("Ошибка парсинга поля {field} в строке {index}, столбце {column}")]
     InvalidField {
         /// Индекс строки
         index: usize,
+        /// Смещение начала поля в строке в байтах
+        column: usize,
         /// Поле
         field: Field,
     },
+    /// Превышена максимальная длина строки
+    #[error("Превышена максимальная длина строки {limit} байт в строке {index}")]
+    LineTooLong {
+        /// Индекс строки
+        index: usize,
+        /// Ограничение
+        limit: usize,
+    },
+    /// Превышен максимальный размер входных данных
+    #[error("Превышен максимальный размер входных данных {limit} байт")]
+    InputTooLarge {
+        /// Ограничение
+        limit: u64,
+    },
+    /// Превышено максимальное количество записей
+    #[error("Превышено максимальное количество записей {limit}")]
+    TooManyRecords {
+        /// Ограничение
+        limit: usize,
+    },
+    /// Превышена максимальная длина описания
+    #[error(
+        "Превышена максимальная длина описания {limit} байт в строке {index}, столбце {column}"
+    )]
+    DescriptionTooLong {
+        /// Индекс строки
+        index: usize,
+        /// Смещение начала поля в строке в байтах
+        column: usize,
+        /// Ограничение
+        limit: usize,
+    },
+}
+
+fn join_fields(fields: &[Field]) -> String {
+    fields
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
 }
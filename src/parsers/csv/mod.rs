@@ -1,4 +1,7 @@
+/// Модуль диалекта
+pub mod dialect;
 /// Модуль ошибок
 pub mod error;
 /// Модуль парсера
 pub mod parser;
+mod tokenizer;
@@ -1,113 +1,507 @@
-use std::io::{Read, Write};
+use std::io::{BufRead, Write};
 
 use crate::{
-    Field, Parser, Status, Transaction, TxType,
-    errors::WriteError,
-    parsers::{csv::error::CsvError, utils::description_trim},
+    Field, Format, ParseLimits, ParseMode, ParseOptions, Parser, Transaction, TransactionWriter,
+    builder::TransactionBuilder,
+    errors::{ParserError, WriteError},
+    parsers::{
+        csv::{
+            dialect::CsvDialect,
+            error::CsvError,
+            tokenizer::{CsvField, DELIMITER, QUOTE, Tokenizer, quote},
+        },
+        utils::{LineError, LineReader, sniff_text, trim_line_end},
+    },
 };
 
+const BOM: char = '\u{feff}';
+
 /// Парсер для csv формата
-pub struct CsvParser;
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CsvParser {
+    options: ParseOptions,
+    dialect: CsvDialect,
+}
 
-impl Parser for CsvParser {
-    type Error = CsvError;
+impl CsvParser {
+    /// Создание парсера с настройками по умолчанию
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Установка режима разбора
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.options.mode = mode;
+        self
+    }
+
+    /// Установка ограничений на размер входных данных
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.options.limits = limits;
+        self
+    }
+
+    /// Установка диалекта
+    pub fn with_dialect(mut self, dialect: CsvDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Установка разделителя полей
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.dialect.delimiter = delimiter;
+        self
+    }
+}
+
+/// Потоковое чтение записей csv формата
+pub struct CsvRecords<R> {
+    reader: R,
+    mode: ParseMode,
+    limits: ParseLimits,
+    dialect: CsvDialect,
+    lines: LineReader,
+    line: Vec<u8>,
+    /// Поля столбцов. `None` для пропускаемых столбцов, пусто до чтения заголовка
+    columns: Option<Vec<Option<Field>>>,
+    index: usize,
+    count: usize,
+    /// Ошибка произошла внутри многострочного поля в кавычках,
+    /// и оставшиеся строки этой записи нужно пропустить
+    skip_quoted: bool,
+    finished: bool,
+}
+
+impl<R: BufRead> CsvRecords<R> {
+    /// Создание итератора по записям
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            mode: ParseMode::Strict,
+            limits: ParseLimits::default(),
+            dialect: CsvDialect::default(),
+            lines: LineReader::new(ParseLimits::default()),
+            line: Vec::new(),
+            columns: None,
+            index: 0,
+            count: 0,
+            skip_quoted: false,
+            finished: false,
+        }
+    }
+
+    /// Установка режима разбора
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Установка ограничений на размер входных данных
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self.lines = LineReader::new(limits);
+        self
+    }
 
-    fn from_read<R: Read>(reader: &mut R) -> Result<Vec<Transaction>, CsvError> {
-        let mut content = String::new();
-        reader
-            .read_to_string(&mut content)
-            .map_err(|_| CsvError::Read)?;
-
-        let mut transactions: Vec<Transaction> = Vec::new();
-
-        for (index, line) in content.lines().enumerate() {
-            if index == 0 {
-                if line == get_header_row() {
-                    continue;
-                } else {
-                    return Err(CsvError::Header);
-                }
+    /// Установка диалекта
+    pub fn with_dialect(mut self, dialect: CsvDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Установка разделителя полей
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.dialect.delimiter = delimiter;
+        self
+    }
+
+    fn read_line(&mut self, index: usize) -> Result<bool, CsvError> {
+        let result = self.lines.read(&mut self.reader, &mut self.line);
+        match result {
+            Ok(has_line) => Ok(has_line),
+            Err(LineError::Io(source)) => Err(CsvError::Read { index, source }),
+            Err(LineError::LineTooLong(limit)) => {
+                self.index += 1;
+                Err(CsvError::LineTooLong { index, limit })
             }
+            Err(LineError::InputTooLarge(limit)) => Err(CsvError::InputTooLarge { limit }),
+        }
+    }
 
-            if line.is_empty() {
-                continue;
+    /// Пропуск строк записи после ошибки внутри поля в кавычках.
+    /// Строка с ошибкой уже прочитана, запись заканчивается на строке,
+    /// после которой закрыты все кавычки
+    fn skip_record(&mut self) -> Result<(), CsvError> {
+        let mut quoted = true;
+        loop {
+            let quotes = String::from_utf8_lossy(&self.line)
+                .matches(self.dialect.quote)
+                .count();
+            quoted ^= quotes % 2 == 1;
+            if !quoted {
+                return Ok(());
             }
 
-            let values: Vec<&str> = line.split(",").collect();
+            self.lines.reset();
+            let index = self.index;
+            match self.read_line(index) {
+                Ok(true) => self.index += 1,
+                Ok(false) => return Ok(()),
+                Err(CsvError::LineTooLong { .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn read_record(&mut self) -> Result<Option<Transaction>, CsvError> {
+        if self.skip_quoted {
+            self.skip_quoted = false;
+            self.skip_record()?;
+        }
+
+        let mut tokenizer = Tokenizer::new()
+            .with_delimiter(self.dialect.delimiter)
+            .with_quote(self.dialect.quote);
+        let result = self.read_record_with(&mut tokenizer);
+        self.skip_quoted = result.is_err() && tokenizer.in_progress();
+        result
+    }
+
+    fn read_record_with(
+        &mut self,
+        tokenizer: &mut Tokenizer,
+    ) -> Result<Option<Transaction>, CsvError> {
+        loop {
+            let index = self.index;
 
-            if values.len() != 8 {
-                return Err(CsvError::Length { index });
+            if !tokenizer.in_progress() {
+                self.lines.reset();
             }
+            if !self.read_line(index)? {
+                tokenizer.finish()?;
+                return Ok(None);
+            }
+
+            self.index += 1;
 
-            let parse_col_u64 = |i: usize, field: Field| {
-                values[i]
-                    .parse::<u64>()
-                    .map_err(|_| CsvError::InvalidField { index, field })
+            let line = str::from_utf8(&self.line).map_err(|source| CsvError::Utf8 {
+                index,
+                column: source.valid_up_to(),
+                source,
+            })?;
+            let line = match index {
+                0 => line.strip_prefix(BOM).unwrap_or(line),
+                _ => line,
             };
-            let parse_col_i64 = |i: usize, field: Field| {
-                values[i]
-                    .parse::<i64>()
-                    .map_err(|_| CsvError::InvalidField { index, field })
+
+            if !tokenizer.in_progress() && trim_line_end(line.as_bytes()).is_empty() {
+                continue;
+            }
+
+            let Some(fields) = tokenizer.feed(index, line)? else {
+                continue;
             };
 
-            transactions.push(Transaction {
-                tx_id: parse_col_u64(0, Field::TxId)?,
-                tx_type: values[1]
-                    .parse::<TxType>()
-                    .map_err(|_| CsvError::InvalidField {
-                        index,
-                        field: Field::TxType,
-                    })?,
-                from_user_id: parse_col_u64(2, Field::FromUserId)?,
-                to_user_id: parse_col_u64(3, Field::ToUserId)?,
-                amount: parse_col_u64(4, Field::Amount)?,
-                timestamp: parse_col_i64(5, Field::Timestamp)?,
-                status: values[6]
-                    .parse::<Status>()
-                    .map_err(|_| CsvError::InvalidField {
-                        index,
-                        field: Field::Status,
-                    })?,
-                description: description_trim(values[7]).map_err(|_| CsvError::InvalidField {
-                    index,
-                    field: Field::Description,
-                })?,
-            });
+            let columns = match self.columns.take() {
+                Some(columns) => columns,
+                None => match parse_header(&fields, &self.dialect)? {
+                    Some(columns) => {
+                        self.columns = Some(columns);
+                        continue;
+                    }
+                    None => Field::get_all().map(Some).to_vec(),
+                },
+            };
+            let result = parse_fields(fields, &columns, &self.limits);
+            self.columns = Some(columns);
+            return result.map(Some);
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for CsvRecords<R> {
+    type Item = Result<Transaction, CsvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let mut result = self.read_record().transpose();
+        if result.is_some()
+            && let Some(limit) = self.limits.max_records
+            && self.count >= limit
+        {
+            result = Some(Err(CsvError::TooManyRecords { limit }));
+        }
+        self.count += 1;
+        self.finished = match &result {
+            None => true,
+            Some(Ok(_)) => false,
+            Some(Err(e)) => self.mode == ParseMode::Strict || !is_recoverable(e),
+        };
+        result
+    }
+}
+
+/// Ошибки, после которых можно продолжить чтение со следующей строки
+fn is_recoverable(error: &CsvError) -> bool {
+    matches!(
+        error,
+        CsvError::Utf8 { .. }
+            | CsvError::InvalidQuote { .. }
+            | CsvError::Length { .. }
+            | CsvError::InvalidField { .. }
+            | CsvError::LineTooLong { .. }
+            | CsvError::DescriptionTooLong { .. }
+    )
+}
+
+/// Поле по имени столбца без учёта регистра
+fn find_field(name: &str) -> Option<Field> {
+    Field::get_all()
+        .into_iter()
+        .find(|field| field.to_string().eq_ignore_ascii_case(name.trim()))
+}
+
+/// Сопоставление столбцов заголовка с полями.
+/// Возвращает `None`, если заголовок необязателен и первая запись не похожа на заголовок
+fn parse_header(
+    fields: &[CsvField],
+    dialect: &CsvDialect,
+) -> Result<Option<Vec<Option<Field>>>, CsvError> {
+    let index = fields.first().map(|f| f.index).unwrap_or_default();
+    let names: Vec<Option<Field>> = fields.iter().map(|f| find_field(&f.value)).collect();
+    if names.iter().all(Option::is_none) {
+        return match dialect.header_required {
+            true => Err(CsvError::Header),
+            false => Ok(None),
+        };
+    }
+
+    let mut columns = Vec::with_capacity(names.len());
+    for (value, field) in fields.iter().zip(names) {
+        match field {
+            Some(field) if columns.contains(&Some(field.clone())) => {
+                return Err(CsvError::DuplicateColumn {
+                    index: value.index,
+                    column: value.column,
+                    field,
+                });
+            }
+            None if !dialect.ignore_unknown_columns => {
+                return Err(CsvError::UnknownColumn {
+                    index: value.index,
+                    column: value.column,
+                    name: value.value.clone(),
+                });
+            }
+            field => columns.push(field),
         }
+    }
+
+    let missing: Vec<Field> = Field::get_all()
+        .into_iter()
+        .filter(|field| !columns.contains(&Some(field.clone())))
+        .collect();
+    if !missing.is_empty() {
+        return Err(CsvError::MissingColumns {
+            index,
+            columns: missing,
+        });
+    }
 
-        Ok(transactions)
-    }
-
-    fn write_to<W: Write>(writer: &mut W, transactions: &[Transaction]) -> Result<(), WriteError> {
-        writeln!(writer, "{}", get_header_row()).map_err(|_| WriteError::Write)?;
-        for t in transactions {
-            let line = format!(
-                "{},{},{},{},{},{},{},\"{}\"",
-                t.tx_id,
-                t.tx_type,
-                t.from_user_id,
-                t.to_user_id,
-                t.amount,
-                t.timestamp,
-                t.status,
-                t.description
-            );
-            writeln!(writer, "{line}").map_err(|_| WriteError::Write)?;
+    Ok(Some(columns))
+}
+
+fn parse_fields(
+    fields: Vec<CsvField>,
+    columns: &[Option<Field>],
+    limits: &ParseLimits,
+) -> Result<Transaction, CsvError> {
+    let index = fields.first().map(|f| f.index).unwrap_or_default();
+    if fields.len() != columns.len() {
+        return Err(CsvError::Length { index });
+    }
+
+    let mut builder = TransactionBuilder::new();
+    for (value, field) in fields.into_iter().zip(columns) {
+        let Some(field) = field else {
+            continue;
+        };
+        let invalid_field = || CsvError::InvalidField {
+            index: value.index,
+            column: value.column,
+            field: field.clone(),
+        };
+
+        if *field == Field::Description {
+            if !value.quoted {
+                return Err(invalid_field());
+            }
+            if let Some(limit) = limits.max_description_bytes
+                && value.value.len() > limit
+            {
+                return Err(CsvError::DescriptionTooLong {
+                    index: value.index,
+                    column: value.column,
+                    limit,
+                });
+            }
+            builder = builder.description(value.value);
+        } else {
+            builder = builder
+                .value(field, &value.value)
+                .map_err(|_| invalid_field())?;
+        }
+    }
+
+    builder.build().map_err(|_| CsvError::Length { index })
+}
+
+impl Parser for CsvParser {
+    type Error = CsvError;
+
+    fn records_with_options<R: BufRead>(
+        reader: R,
+        options: ParseOptions,
+    ) -> impl Iterator<Item = Result<Transaction, CsvError>> {
+        CsvRecords::new(reader)
+            .with_mode(options.mode)
+            .with_limits(options.limits)
+    }
+
+    fn writer<W: Write>(writer: W) -> impl TransactionWriter {
+        CsvWriter::new(writer)
+    }
+}
+
+impl Format for CsvParser {
+    fn parse_options(&self) -> ParseOptions {
+        self.options
+    }
+
+    fn with_parse_options(&self, options: ParseOptions) -> Box<dyn Format> {
+        Box::new(Self { options, ..*self })
+    }
+
+    fn read_records<'a>(
+        &self,
+        reader: &'a mut dyn BufRead,
+    ) -> Box<dyn Iterator<Item = Result<Transaction, ParserError>> + 'a> {
+        let records = CsvRecords::new(reader)
+            .with_mode(self.options.mode)
+            .with_limits(self.options.limits)
+            .with_dialect(self.dialect);
+        Box::new(records.map(|r| Ok(r?)))
+    }
+
+    fn create_writer<'a>(&self, writer: &'a mut dyn Write) -> Box<dyn TransactionWriter + 'a> {
+        Box::new(CsvWriter::new(writer).with_dialect(self.dialect))
+    }
+}
+
+/// Потоковая запись транзакций в csv формате
+pub struct CsvWriter<W> {
+    writer: W,
+    dialect: CsvDialect,
+    header_written: bool,
+}
+
+impl<W: Write> CsvWriter<W> {
+    /// Создание писателя
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            dialect: CsvDialect::default(),
+            header_written: false,
+        }
+    }
+
+    /// Установка диалекта
+    pub fn with_dialect(mut self, dialect: CsvDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Установка разделителя полей
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.dialect.delimiter = delimiter;
+        self
+    }
+
+    /// Получение исходного потока
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_header(&mut self) -> Result<(), WriteError> {
+        if !self.header_written {
+            if self.dialect.write_bom {
+                write!(self.writer, "{BOM}").map_err(WriteError::Write)?;
+            }
+            let header = header_row(self.dialect.delimiter);
+            writeln!(self.writer, "{header}").map_err(WriteError::Write)?;
+            self.header_written = true;
         }
-        writer.flush().map_err(|_| WriteError::Write)?;
         Ok(())
     }
 }
 
-fn get_header_row() -> String {
-    Field::get_all().map(|c| c.to_string()).join(",")
+impl<W: Write> TransactionWriter for CsvWriter<W> {
+    fn write(&mut self, t: &Transaction) -> Result<(), WriteError> {
+        self.write_header()?;
+        let line = Field::get_all()
+            .map(|field| match field {
+                Field::Description => quote(&t.description, self.dialect.quote),
+                _ => t.get_value(&field),
+            })
+            .join(&self.dialect.delimiter.to_string());
+        writeln!(self.writer, "{line}").map_err(WriteError::Write)
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        self.write_header()?;
+        self.writer.flush().map_err(WriteError::Write)
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn get_header_row() -> String {
+    header_row(DELIMITER)
+}
+
+fn header_row(delimiter: char) -> String {
+    Field::get_all()
+        .map(|c| c.to_string())
+        .join(&delimiter.to_string())
+}
+
+/// Файл начинается со строки заголовка диалекта по умолчанию.
+/// Столбцы могут идти в любом порядке и в любом регистре
+pub(crate) fn sniff(data: &[u8]) -> bool {
+    let text = sniff_text(data);
+    let Some(line) = text.lines().next() else {
+        return false;
+    };
+    let columns: Vec<Option<Field>> = line
+        .split(DELIMITER)
+        .map(|name| find_field(name.trim_matches(QUOTE)))
+        .collect();
+    columns.iter().all(Option::is_some)
+        && Field::get_all()
+            .into_iter()
+            .all(|field| columns.contains(&Some(field)))
 }
 
 #[cfg(test)]
 mod tests_from_read {
-    use std::io::Cursor;
+    use std::{
+        error::Error,
+        io::{self, BufReader, Cursor, Read},
+    };
 
     use super::*;
+    use crate::{Status, TxType};
 
     fn get_cursor(data: &str) -> Cursor<String> {
         let header = get_header_row();
@@ -133,6 +527,130 @@ mod tests_from_read {
         );
     }
 
+    #[test]
+    fn test_success_records() {
+        let cursor = get_cursor(
+            "1,DEPOSIT,0,1,100,1633036860000,SUCCESS,\"Test 1\"\r\n\n2,!,0,1,100,1633036860000,SUCCESS,\"Test 2\"\n3,DEPOSIT,0,1,100,1633036860000,SUCCESS,\"Test 3\"",
+        );
+        let mut records = CsvParser::records(BufReader::new(cursor));
+        assert_eq!(records.next().unwrap().unwrap().tx_id, 1);
+        assert_eq!(
+            records.next().unwrap().unwrap_err().to_string(),
+            "Ошибка парсинга поля TX_TYPE в строке 3, столбце 2"
+        );
+        assert!(records.next().is_none());
+    }
+
+    #[test]
+    fn test_success_records_lenient() {
+        let cursor = get_cursor(
+            "1,DEPOSIT,0,1,100,1633036860000,SUCCESS,\"Test 1\"\n2,!,0,1,100,1633036860000,SUCCESS,\"Test 2\"\n3\n4,DEPOSIT,0,1,100,1633036860000,SUCCESS,\"Test 4\"",
+        );
+        let result: Vec<_> =
+            CsvParser::records_with_mode(BufReader::new(cursor), ParseMode::Lenient)
+                .map(|r| r.map(|t| t.tx_id).map_err(|e| e.to_string()))
+                .collect();
+        assert_eq!(
+            result,
+            [
+                Ok(1),
+                Err("Ошибка парсинга поля TX_TYPE в строке 2, столбце 2".to_string()),
+                Err("Некорректное количество элементов в строке 3".to_string()),
+                Ok(4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_success_records_lenient_multiline() {
+        let record = |tx_id: u64, description: &str| {
+            format!("{tx_id},DEPOSIT,0,1,100,1633036860000,SUCCESS,\"{description}\"\n")
+        };
+        let mut data = get_header_row().into_bytes();
+        data.push(b'\n');
+        data.extend(record(1, "Test 1").as_bytes());
+        data.extend(record(2, &format!("first\n{}\n3,\"\"x\"\"", "a".repeat(200))).as_bytes());
+        data.extend(record(4, "bad\n\u{1F600}").as_bytes());
+        // некорректный UTF-8 в середине многострочного описания
+        let emoji = data.len() - 6;
+        data[emoji] = 0xff;
+        data.extend(record(5, "Test 5").as_bytes());
+
+        let options = ParseOptions {
+            mode: ParseMode::Lenient,
+            limits: ParseLimits {
+                max_line_length: Some(100),
+                ..Default::default()
+            },
+        };
+        let result: Vec<_> = CsvParser::records_with_options(Cursor::new(data), options)
+            .map(|r| r.map(|t| t.tx_id).map_err(|e| e.to_string()))
+            .collect();
+        assert_eq!(
+            result,
+            [
+                Ok(1),
+                Err("Превышена максимальная длина строки 100 байт в строке 3".to_string()),
+                Err("Некорректная кодировка UTF-8 в строке 6, столбце 0".to_string()),
+                Ok(5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_limits() {
+        let cursor = get_cursor(&format!(
+            "1,DEPOSIT,0,1,100,1633036860000,SUCCESS,\"{}\"\n2,DEPOSIT,0,1,100,1633036860000,SUCCESS,\"{}\"\n3,DEPOSIT,0,1,100,1633036860000,SUCCESS,\"Test 3\"\n4,DEPOSIT,0,1,100,1633036860000,SUCCESS,\"Test 4\"",
+            "a".repeat(20),
+            "b".repeat(1000),
+        ));
+        let options = ParseOptions {
+            mode: ParseMode::Lenient,
+            limits: ParseLimits {
+                max_description_bytes: Some(10),
+                max_records: Some(3),
+                max_line_length: Some(100),
+                ..Default::default()
+            },
+        };
+        let result: Vec<_> = CsvParser::records_with_options(BufReader::new(cursor), options)
+            .map(|r| r.map(|t| t.tx_id).map_err(|e| e.to_string()))
+            .collect();
+        assert_eq!(
+            result,
+            [
+                Err(
+                    "Превышена максимальная длина описания 10 байт в строке 1, столбце 40"
+                        .to_string()
+                ),
+                Err("Превышена максимальная длина строки 100 байт в строке 2".to_string()),
+                Ok(3),
+                Err("Превышено максимальное количество записей 3".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_input_too_large() {
+        let cursor = get_cursor("1,DEPOSIT,0,1,100,1633036860000,SUCCESS,\"Test 1\"");
+        let options = ParseOptions {
+            mode: ParseMode::Lenient,
+            limits: ParseLimits {
+                max_input_bytes: Some(100),
+                ..Default::default()
+            },
+        };
+        let result: Vec<_> = CsvParser::records_with_options(BufReader::new(cursor), options)
+            .map(|r| r.map_err(|e| e.to_string()))
+            .collect();
+        assert_eq!(
+            result,
+            [Err(
+                "Превышен максимальный размер входных данных 100 байт".to_string()
+            )]
+        );
+    }
+
     #[test]
     fn test_get_header_row() {
         assert_eq!(
@@ -143,10 +661,94 @@ mod tests_from_read {
 
     #[test]
     fn test_error_header() {
-        let data = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,DESCRIPTION,STATUS";
-        let mut cursor = Cursor::new(data);
-        let result = CsvParser::from_read(&mut cursor).unwrap_err();
-        assert_eq!(result.to_string(), "Некорректный заголовок");
+        let cases = [
+            (
+                "1,DEPOSIT,0,1,100,1633036860000,SUCCESS,\"Test 1\"",
+                "Некорректный заголовок",
+            ),
+            (
+                "tx_id,TX_TYPE,amount",
+                "В заголовке в строке 0 отсутствуют столбцы: FROM_USER_ID, TO_USER_ID, TIMESTAMP, STATUS, DESCRIPTION",
+            ),
+            (
+                "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION,COMMENT",
+                "Неизвестный столбец COMMENT в заголовке в строке 0, столбце 74",
+            ),
+            (
+                "\n\nTX_ID,TX_TYPE,tx_id",
+                "Повторяющийся столбец TX_ID в заголовке в строке 2, столбце 14",
+            ),
+        ];
+        for (data, expected) in cases {
+            let result = CsvParser::from_read(&mut Cursor::new(data)).unwrap_err();
+            assert_eq!(result.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_success_dialect() {
+        let data = "\u{feff}status;Amount;TX_TYPE;FROM_USER_ID;TO_USER_ID;Comment;TIMESTAMP;DESCRIPTION;TX_ID\r\n\
+                    SUCCESS;100;DEPOSIT;0;1;x;1633036860000;'Test ''1''';7\r\n";
+        let dialect = CsvDialect {
+            delimiter: ';',
+            quote: '\'',
+            ignore_unknown_columns: true,
+            ..Default::default()
+        };
+        let format = CsvParser::new().with_dialect(dialect);
+        let (result, _) = format.read_transactions(&mut data.as_bytes()).unwrap();
+        assert_eq!(
+            result,
+            [Transaction {
+                tx_id: 7,
+                tx_type: TxType::Deposit,
+                from_user_id: 0,
+                to_user_id: 1,
+                amount: 100,
+                timestamp: 1633036860000,
+                status: Status::Success,
+                description: "Test '1'".to_string(),
+            }]
+        );
+
+        let data = "1,DEPOSIT,0,1,100,1633036860000,SUCCESS,\"Test 1\"\n";
+        let dialect = CsvDialect {
+            header_required: false,
+            ..Default::default()
+        };
+        let format = CsvParser::new().with_dialect(dialect);
+        let (result, _) = format.read_transactions(&mut data.as_bytes()).unwrap();
+        assert_eq!(result[0].tx_id, 1);
+        let data = format!("{}\n{data}", get_header_row());
+        let (result, _) = format.read_transactions(&mut data.as_bytes()).unwrap();
+        assert_eq!(result[0].tx_id, 1);
+    }
+
+    #[test]
+    fn test_error_read() {
+        struct FailingReader;
+
+        impl Read for FailingReader {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::from(io::ErrorKind::PermissionDenied))
+            }
+        }
+
+        let result = CsvParser::from_read(&mut FailingReader).unwrap_err();
+        let source = result
+            .source()
+            .and_then(|e| e.downcast_ref::<io::Error>())
+            .unwrap();
+        assert_eq!(source.kind(), io::ErrorKind::PermissionDenied);
+
+        let mut data = format!("{}\n0,DEPOSIT,", get_header_row()).into_bytes();
+        data.extend_from_slice(&[0xff, 0xfe]);
+        let result = CsvParser::from_read(&mut Cursor::new(data)).unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Некорректная кодировка UTF-8 в строке 1, столбце 10"
+        );
+        assert!(result.source().unwrap().is::<std::str::Utf8Error>());
     }
 
     #[test]
@@ -163,58 +765,68 @@ mod tests_from_read {
     fn test_error_invalid_field() {
         let mut cursor = get_cursor("!,DEPOSIT,0,1,100,1633036860000,FAILURE,\"Test\"");
         let result = CsvParser::from_read(&mut cursor).unwrap_err();
-        assert_eq!(result.to_string(), "Ошибка парсинга поля TX_ID в строке 1");
+        assert_eq!(
+            result.to_string(),
+            "Ошибка парсинга поля TX_ID в строке 1, столбце 0"
+        );
 
         let mut cursor = get_cursor("0,!,0,1,100,1633036860000,FAILURE,\"Test\"");
         let result = CsvParser::from_read(&mut cursor).unwrap_err();
         assert_eq!(
             result.to_string(),
-            "Ошибка парсинга поля TX_TYPE в строке 1"
+            "Ошибка парсинга поля TX_TYPE в строке 1, столбце 2"
         );
 
         let mut cursor = get_cursor("0,DEPOSIT,!,1,100,1633036860000,FAILURE,\"Test\"");
         let result = CsvParser::from_read(&mut cursor).unwrap_err();
         assert_eq!(
             result.to_string(),
-            "Ошибка парсинга поля FROM_USER_ID в строке 1"
+            "Ошибка парсинга поля FROM_USER_ID в строке 1, столбце 10"
         );
 
         let mut cursor = get_cursor("0,DEPOSIT,0,!,100,1633036860000,FAILURE,\"Test\"");
         let result = CsvParser::from_read(&mut cursor).unwrap_err();
         assert_eq!(
             result.to_string(),
-            "Ошибка парсинга поля TO_USER_ID в строке 1"
+            "Ошибка парсинга поля TO_USER_ID в строке 1, столбце 12"
         );
 
         let mut cursor = get_cursor("0,DEPOSIT,0,1,!,1633036860000,FAILURE,\"Test\"");
         let result = CsvParser::from_read(&mut cursor).unwrap_err();
-        assert_eq!(result.to_string(), "Ошибка парсинга поля AMOUNT в строке 1");
+        assert_eq!(
+            result.to_string(),
+            "Ошибка парсинга поля AMOUNT в строке 1, столбце 14"
+        );
 
         let mut cursor = get_cursor("0,DEPOSIT,0,1,100,!,FAILURE,\"Test\"");
         let result = CsvParser::from_read(&mut cursor).unwrap_err();
         assert_eq!(
             result.to_string(),
-            "Ошибка парсинга поля TIMESTAMP в строке 1"
+            "Ошибка парсинга поля TIMESTAMP в строке 1, столбце 18"
         );
 
         let mut cursor = get_cursor("0,DEPOSIT,0,1,100,1633036860000,!,\"Test\"");
         let result = CsvParser::from_read(&mut cursor).unwrap_err();
-        assert_eq!(result.to_string(), "Ошибка парсинга поля STATUS в строке 1");
+        assert_eq!(
+            result.to_string(),
+            "Ошибка парсинга поля STATUS в строке 1, столбце 32"
+        );
 
         let mut cursor = get_cursor("0,DEPOSIT,0,1,100,1633036860000,FAILURE,\'Test\'");
         let result = CsvParser::from_read(&mut cursor).unwrap_err();
         assert_eq!(
             result.to_string(),
-            "Ошибка парсинга поля DESCRIPTION в строке 1"
+            "Ошибка парсинга поля DESCRIPTION в строке 1, столбце 40"
         );
     }
 }
 
 #[cfg(test)]
 mod tests_write_to {
-    use std::io::Cursor;
+    use std::io::{Cursor, Read};
 
     use super::*;
+    use crate::{Status, TxType};
 
     #[test]
     fn test_success_write_to() {
@@ -257,3 +869,105 @@ mod tests_write_to {
         );
     }
 }
+
+#[cfg(test)]
+mod tests_writer {
+    use super::*;
+
+    #[test]
+    fn test_success_writer() {
+        let mut writer = CsvWriter::new(Vec::new());
+        writer.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            format!("{}\n", get_header_row())
+        );
+
+        let mut writer = CsvWriter::new(Vec::new());
+        for tx_id in 1..=2 {
+            writer
+                .write(&Transaction {
+                    tx_id,
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        writer.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            [
+                get_header_row().as_str(),
+                "1,DEPOSIT,0,0,0,0,SUCCESS,\"\"",
+                "2,DEPOSIT,0,0,0,0,SUCCESS,\"\"",
+            ]
+            .map(|l| format!("{l}\n"))
+            .join("")
+        );
+    }
+
+    #[test]
+    fn test_success_writer_dialect() {
+        let mut writer = CsvWriter::new(Vec::new()).with_dialect(CsvDialect::excel_ru());
+        writer
+            .write(&Transaction {
+                description: "a; \"b\"".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        writer.finish().unwrap();
+        let data = writer.into_inner();
+        assert_eq!(
+            String::from_utf8(data.clone()).unwrap(),
+            "\u{feff}TX_ID;TX_TYPE;FROM_USER_ID;TO_USER_ID;AMOUNT;TIMESTAMP;STATUS;DESCRIPTION\n\
+             0;DEPOSIT;0;0;0;0;SUCCESS;\"a; \"\"b\"\"\"\n"
+        );
+
+        let format = CsvParser::new().with_dialect(CsvDialect::excel_ru());
+        let (result, _) = format.read_transactions(&mut data.as_slice()).unwrap();
+        assert_eq!(result[0].description, "a; \"b\"");
+    }
+}
+
+#[cfg(test)]
+mod tests_round_trip {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_success_round_trip() {
+        let transactions: Vec<Transaction> = [
+            "comma, inside",
+            "\"quoted\" and \"\"doubled\"\"",
+            "multi\nline\r\ndescription",
+            "\"",
+            "",
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(tx_id, description)| Transaction {
+            tx_id: tx_id as u64,
+            description: description.to_string(),
+            ..Default::default()
+        })
+        .collect();
+
+        let mut data = Vec::new();
+        CsvParser::write_to(&mut data, &transactions).unwrap();
+        let result = CsvParser::from_read(&mut Cursor::new(data)).unwrap();
+        assert_eq!(result, transactions);
+    }
+
+    #[test]
+    fn test_error_unterminated_quote() {
+        let data = format!(
+            "{}\n0,DEPOSIT,0,1,100,0,SUCCESS,\"open\n\n",
+            get_header_row()
+        );
+        let result = CsvParser::from_read(&mut Cursor::new(data)).unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Незакрытая кавычка в строке 1, столбце 28"
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use crate::parsers::csv::error::CsvError;
+
+/// Разделитель полей по умолчанию
+pub const DELIMITER: char = ',';
+/// Символ кавычек по умолчанию
+pub const QUOTE: char = '"';
+
+/// Поле csv записи
+#[derive(Debug, PartialEq)]
+pub struct CsvField {
+    /// Значение поля без кавычек и экранирования
+    pub value: String,
+    /// Было ли поле заключено в кавычки
+    pub quoted: bool,
+    /// Индекс строки, в которой начинается поле
+    pub index: usize,
+    /// Смещение начала поля в строке в байтах
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    FieldStart,
+    Unquoted,
+    Quoted,
+    QuoteInQuoted,
+}
+
+/// Разбор csv записи по RFC 4180.
+/// Запись может занимать несколько строк, если перевод строки находится внутри кавычек
+pub struct Tokenizer {
+    delimiter: char,
+    quote: char,
+    state: State,
+    fields: Vec<CsvField>,
+    field: Option<CsvField>,
+}
+
+impl Tokenizer {
+    pub fn new() -> Self {
+        Self {
+            delimiter: DELIMITER,
+            quote: QUOTE,
+            state: State::FieldStart,
+            fields: Vec::new(),
+            field: None,
+        }
+    }
+
+    /// Установка разделителя полей
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Установка символа кавычек
+    pub fn with_quote(mut self, quote: char) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Начата ли запись, продолжение которой находится на следующих строках
+    pub fn in_progress(&self) -> bool {
+        self.state == State::Quoted
+    }
+
+    /// Обработка очередной строки вместе с символами перевода строки.
+    /// Возвращает поля, если запись закончилась на этой строке
+    pub fn feed(&mut self, index: usize, line: &str) -> Result<Option<Vec<CsvField>>, CsvError> {
+        let mut chars = line.char_indices().peekable();
+
+        while let Some((column, c)) = chars.next() {
+            let is_line_end = match c {
+                '\n' => true,
+                '\r' => matches!(chars.peek(), Some((_, '\n'))),
+                _ => false,
+            };
+
+            match self.state {
+                State::FieldStart | State::Unquoted if is_line_end => break,
+                State::QuoteInQuoted if is_line_end => break,
+                State::FieldStart => {
+                    let field = self.field.insert(CsvField {
+                        value: String::new(),
+                        quoted: c == self.quote,
+                        index,
+                        column,
+                    });
+                    if c == self.delimiter {
+                        self.push_field();
+                    } else if c == self.quote {
+                        self.state = State::Quoted;
+                    } else {
+                        field.value.push(c);
+                        self.state = State::Unquoted;
+                    }
+                }
+                State::Unquoted => match c {
+                    c if c == self.delimiter => self.push_field(),
+                    c if c == self.quote => return Err(self.invalid_quote(index, column)),
+                    _ => self.push_char(c),
+                },
+                State::Quoted => match c {
+                    c if c == self.quote => self.state = State::QuoteInQuoted,
+                    _ => self.push_char(c),
+                },
+                State::QuoteInQuoted => match c {
+                    c if c == self.quote => {
+                        self.push_char(c);
+                        self.state = State::Quoted;
+                    }
+                    c if c == self.delimiter => self.push_field(),
+                    _ => return Err(self.invalid_quote(index, column)),
+                },
+            }
+        }
+
+        if self.state == State::Quoted {
+            return Ok(None);
+        }
+
+        if self.field.is_some() || !self.fields.is_empty() {
+            let field = self.field.take().unwrap_or(CsvField {
+                value: String::new(),
+                quoted: false,
+                index,
+                column: line.trim_end_matches(['\r', '\n']).len(),
+            });
+            self.fields.push(field);
+        }
+        self.state = State::FieldStart;
+        Ok(Some(std::mem::take(&mut self.fields)))
+    }
+
+    /// Завершение разбора при окончании потока
+    pub fn finish(&mut self) -> Result<(), CsvError> {
+        match (&self.state, &self.field) {
+            (State::Quoted, Some(field)) => Err(CsvError::UnterminatedQuote {
+                index: field.index,
+                column: field.column,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn push_char(&mut self, c: char) {
+        if let Some(field) = self.field.as_mut() {
+            field.value.push(c);
+        }
+    }
+
+    fn push_field(&mut self) {
+        if let Some(field) = self.field.take() {
+            self.fields.push(field);
+        }
+        self.state = State::FieldStart;
+    }
+
+    fn invalid_quote(&mut self, index: usize, column: usize) -> CsvError {
+        *self = Self::new()
+            .with_delimiter(self.delimiter)
+            .with_quote(self.quote);
+        CsvError::InvalidQuote { index, column }
+    }
+}
+
+/// Экранирование значения для записи в кавычках
+pub fn quote(value: &str, quote: char) -> String {
+    let escaped = value.replace(quote, &format!("{quote}{quote}"));
+    format!("{quote}{escaped}{quote}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(fields: Vec<CsvField>) -> Vec<(String, bool)> {
+        fields.into_iter().map(|f| (f.value, f.quoted)).collect()
+    }
+
+    #[test]
+    fn test_success_feed() {
+        let mut tokenizer = Tokenizer::new();
+        let fields = tokenizer
+            .feed(0, "1,,\"a,\"\"b\"\"\",c\r\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            fields.iter().map(|f| f.column).collect::<Vec<_>>(),
+            [0, 2, 3, 13]
+        );
+        assert_eq!(
+            values(fields),
+            [
+                ("1".to_string(), false),
+                ("".to_string(), false),
+                ("a,\"b\"".to_string(), true),
+                ("c".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_success_feed_delimiter() {
+        let mut tokenizer = Tokenizer::new().with_delimiter(';');
+        let fields = tokenizer.feed(0, "1;a,b;\"c;d\"\n").unwrap().unwrap();
+        assert_eq!(
+            values(fields),
+            [
+                ("1".to_string(), false),
+                ("a,b".to_string(), false),
+                ("c;d".to_string(), true),
+            ]
+        );
+
+        assert!(tokenizer.feed(1, "\"a\"b").is_err());
+        let fields = tokenizer.feed(2, "\"a\";b\n").unwrap().unwrap();
+        assert_eq!(fields.len(), 2);
+
+        let mut tokenizer = Tokenizer::new().with_quote('\'');
+        let fields = tokenizer.feed(0, "'a,''b''',\"c\"\n").unwrap().unwrap();
+        assert_eq!(
+            values(fields),
+            [("a,'b'".to_string(), true), ("\"c\"".to_string(), false)]
+        );
+    }
+
+    #[test]
+    fn test_success_feed_multiline() {
+        let mut tokenizer = Tokenizer::new();
+        assert!(tokenizer.feed(0, "1,\"a\r\n").unwrap().is_none());
+        assert!(tokenizer.in_progress());
+        assert!(tokenizer.feed(1, "\n").unwrap().is_none());
+        let fields = tokenizer.feed(2, "b\",").unwrap().unwrap();
+        assert_eq!(
+            values(fields),
+            [
+                ("1".to_string(), false),
+                ("a\r\n\nb".to_string(), true),
+                ("".to_string(), false),
+            ]
+        );
+        assert!(!tokenizer.in_progress());
+    }
+
+    #[test]
+    fn test_error_invalid_quote() {
+        let mut tokenizer = Tokenizer::new();
+        let result = tokenizer.feed(3, "1,a\"b").unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Некорректное использование кавычек в строке 3, столбце 3"
+        );
+
+        let result = tokenizer.feed(4, "\"a\"b").unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Некорректное использование кавычек в строке 4, столбце 3"
+        );
+    }
+
+    #[test]
+    fn test_error_unterminated_quote() {
+        let mut tokenizer = Tokenizer::new();
+        assert!(tokenizer.feed(1, "1,\"a\n").unwrap().is_none());
+        let result = tokenizer.finish().unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Незакрытая кавычка в строке 1, столбце 2"
+        );
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("a \"b\", c", QUOTE), "\"a \"\"b\"\", c\"");
+        assert_eq!(quote("a 'b'", '\''), "'a ''b'''");
+    }
+}
@@ -0,0 +1,107 @@
+use std::{io, str::Utf8Error};
+
+use thiserror::Error;
+
+use crate::Field;
+
+/// Возможные ошибки при парсинге json и jsonl форматов
+#[derive(Debug, Error)]
+pub enum JsonError {
+    /// Ошибка чтения
+    #[error("Ошибка чтения в строке {index}: {source}")]
+    Read {
+        /// Индекс строки
+        index: usize,
+        /// Исходная ошибка ввода-вывода
+        #[source]
+        source: io::Error,
+    },
+    /// Строка не является корректной UTF-8 последовательностью
+    #[error("Некорректная кодировка UTF-8 в строке {index}, столбце {column}")]
+    Utf8 {
+        /// Индекс строки
+        index: usize,
+        /// Смещение в строке в байтах
+        column: usize,
+        /// Исходная ошибка декодирования
+        #[source]
+        source: Utf8Error,
+    },
+    /// Некорректный JSON
+    #[error("Некорректный JSON в строке {index}, столбце {column}: {source}")]
+    Syntax {
+        /// Индекс строки
+        index: usize,
+        /// Смещение в строке
+        column: usize,
+        /// Исходная ошибка разбора
+        #[source]
+        source: serde_json::Error,
+    },
+    /// Документ не является массивом
+    #[error("Ожидался массив объектов в строке {index}, столбце {column}")]
+    NotArray {
+        /// Индекс строки
+        index: usize,
+        /// Смещение в строке
+        column: usize,
+    },
+    /// Запись не является объектом
+    #[error("Запись в строке {index} не является объектом")]
+    NotObject {
+        /// Индекс строки
+        index: usize,
+    },
+    /// Неизвестное поле
+    #[error("Неизвестное поле {key} в строке {index}")]
+    UnknownField {
+        /// Индекс строки
+        index: usize,
+        /// Ключ
+        key: String,
+    },
+    /// Пропущено поле
+    #[error("Отсутствует поле {field} в записи на строке {index}")]
+    MissingField {
+        /// Индекс строки
+        index: usize,
+        /// Поле
+        field: Field,
+    },
+    /// Некорректное поле
+    #[error("Ошибка парсинга поля {field} в строке {index}")]
+    InvalidField {
+        /// Индекс строки
+        index: usize,
+        /// Поле
+        field: Field,
+    },
+    /// Превышена максимальная длина строки
+    #[error("Превышена максимальная длина строки {limit} байт в строке {index}")]
+    LineTooLong {
+        /// Индекс строки
+        index: usize,
+        /// Ограничение
+        limit: usize,
+    },
+    /// Превышен максимальный размер входных данных
+    #[error("Превышен максимальный размер входных данных {limit} байт")]
+    InputTooLarge {
+        /// Ограничение
+        limit: u64,
+    },
+    /// Превышено максимальное количество записей
+    #[error("Превышено максимальное количество записей {limit}")]
+    TooManyRecords {
+        /// Ограничение
+        limit: usize,
+    },
+    /// Превышена максимальная длина описания
+    #[error("Превышена максимальная длина описания {limit} байт в строке {index}")]
+    DescriptionTooLong {
+        /// Индекс строки
+        index: usize,
+        /// Ограничение
+        limit: usize,
+    },
+}
@@ -0,0 +1,4 @@
+/// Модуль ошибок
+pub mod error;
+/// Модуль парсера
+pub mod parser;
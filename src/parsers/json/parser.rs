@@ -0,0 +1,613 @@
+use std::io::{BufRead, Read, Write};
+
+use serde_json::{Deserializer, Value};
+
+use crate::{
+    Field, Format, ParseLimits, ParseMode, ParseOptions, Parser, Transaction, TransactionWriter,
+    builder::TransactionBuilder,
+    errors::{ParserError, TransactionBuildError, WriteError},
+    parsers::{json::error::JsonError, utils::sniff_text},
+};
+
+/// Парсер для json формата: массив объектов
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct JsonParser {
+    options: ParseOptions,
+}
+
+impl JsonParser {
+    /// Создание парсера с настройками по умолчанию
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Установка режима разбора
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.options.mode = mode;
+        self
+    }
+
+    /// Установка ограничений на размер входных данных
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.options.limits = limits;
+        self
+    }
+}
+
+/// Чтение записей json формата.
+/// Документ читается в память целиком при первом обращении, записи разбираются по одной
+pub struct JsonRecords<R> {
+    reader: Option<R>,
+    mode: ParseMode,
+    limits: ParseLimits,
+    text: String,
+    position: usize,
+    /// Смещение начала текущей строки
+    line_start: usize,
+    index: usize,
+    count: usize,
+    after_element: bool,
+    ended: bool,
+    finished: bool,
+}
+
+impl<R: BufRead> JsonRecords<R> {
+    /// Создание итератора по записям
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: Some(reader),
+            mode: ParseMode::Strict,
+            limits: ParseLimits::default(),
+            text: String::new(),
+            position: 0,
+            line_start: 0,
+            index: 0,
+            count: 0,
+            after_element: false,
+            ended: false,
+            finished: false,
+        }
+    }
+
+    /// Установка режима разбора
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Установка ограничений на размер входных данных
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Чтение документа и открывающей скобки массива
+    fn load(&mut self) -> Result<(), JsonError> {
+        let Some(reader) = self.reader.take() else {
+            return Ok(());
+        };
+
+        let limit = self.limits.max_input_bytes;
+        let mut data = Vec::new();
+        reader
+            .take(limit.map_or(u64::MAX, |l| l.saturating_add(1)))
+            .read_to_end(&mut data)
+            .map_err(|source| JsonError::Read { index: 0, source })?;
+        if let Some(limit) = limit
+            && data.len() as u64 > limit
+        {
+            return Err(JsonError::InputTooLarge { limit });
+        }
+
+        self.text = String::from_utf8(data).map_err(|e| {
+            let source = e.utf8_error();
+            let valid = &e.as_bytes()[..source.valid_up_to()];
+            let line_start = valid.iter().rposition(|&b| b == b'\n').map_or(0, |p| p + 1);
+            JsonError::Utf8 {
+                index: valid.iter().filter(|&&b| b == b'\n').count(),
+                column: valid.len() - line_start,
+                source,
+            }
+        })?;
+
+        self.skip_whitespace();
+        if !self.rest().starts_with('[') {
+            return Err(self.not_array());
+        }
+        self.advance(1);
+        Ok(())
+    }
+
+    fn rest(&self) -> &str {
+        &self.text[self.position..]
+    }
+
+    fn column(&self) -> usize {
+        self.position - self.line_start
+    }
+
+    fn not_array(&self) -> JsonError {
+        JsonError::NotArray {
+            index: self.index,
+            column: self.column(),
+        }
+    }
+
+    /// Сдвиг позиции на `n` байт с подсчётом строк
+    fn advance(&mut self, n: usize) {
+        let end = self.position + n;
+        let skipped = &self.text[self.position..end];
+        self.index += skipped.matches('\n').count();
+        if let Some(p) = skipped.rfind('\n') {
+            self.line_start = self.position + p + 1;
+        }
+        self.position = end;
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        let n = rest.len() - rest.trim_start().len();
+        self.advance(n);
+    }
+
+    fn read_record(&mut self) -> Result<Option<Transaction>, JsonError> {
+        self.load()?;
+        if self.ended {
+            return Ok(None);
+        }
+
+        self.skip_whitespace();
+        if self.after_element {
+            if self.rest().starts_with(',') {
+                self.advance(1);
+                self.skip_whitespace();
+            } else if self.rest().starts_with(']') {
+                return self.end().map(|_| None);
+            } else {
+                return Err(self.not_array());
+            }
+        } else if self.rest().starts_with(']') {
+            return self.end().map(|_| None);
+        }
+
+        let index = self.index;
+        let column = self.column();
+        let mut values = Deserializer::from_str(self.rest()).into_iter::<Value>();
+        let value = match values.next() {
+            Some(Ok(value)) => value,
+            Some(Err(source)) => {
+                let line = source.line().saturating_sub(1);
+                let error_column = source.column().saturating_sub(1);
+                return Err(JsonError::Syntax {
+                    index: index + line,
+                    column: if line == 0 {
+                        column + error_column
+                    } else {
+                        error_column
+                    },
+                    source,
+                });
+            }
+            None => return Err(self.not_array()),
+        };
+        let consumed = values.byte_offset();
+        self.advance(consumed);
+        self.after_element = true;
+
+        parse_object(value, index, &self.limits).map(Some)
+    }
+
+    /// Закрывающая скобка массива, после которой допустимы только пробельные символы
+    fn end(&mut self) -> Result<(), JsonError> {
+        self.advance(1);
+        self.ended = true;
+        self.skip_whitespace();
+        if !self.rest().is_empty() {
+            return Err(self.not_array());
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for JsonRecords<R> {
+    type Item = Result<Transaction, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let mut result = self.read_record().transpose();
+        if result.is_some()
+            && let Some(limit) = self.limits.max_records
+            && self.count >= limit
+        {
+            result = Some(Err(JsonError::TooManyRecords { limit }));
+        }
+        self.count += 1;
+        self.finished = match &result {
+            None => true,
+            Some(Ok(_)) => false,
+            Some(Err(e)) => self.mode == ParseMode::Strict || !is_recoverable(e),
+        };
+        result
+    }
+}
+
+/// Ошибки, после которых можно продолжить чтение со следующего элемента массива
+fn is_recoverable(error: &JsonError) -> bool {
+    matches!(
+        error,
+        JsonError::NotObject { .. }
+            | JsonError::UnknownField { .. }
+            | JsonError::MissingField { .. }
+            | JsonError::InvalidField { .. }
+            | JsonError::DescriptionTooLong { .. }
+    )
+}
+
+/// Файл начинается с массива
+pub(crate) fn sniff(data: &[u8]) -> bool {
+    sniff_text(data).trim_start().starts_with('[')
+}
+
+/// Разбор объекта с ключами, совпадающими с именами [`Field`]
+pub(crate) fn parse_object(
+    value: Value,
+    index: usize,
+    limits: &ParseLimits,
+) -> Result<Transaction, JsonError> {
+    let Value::Object(object) = value else {
+        return Err(JsonError::NotObject { index });
+    };
+
+    let mut builder = TransactionBuilder::new();
+    for (key, value) in object {
+        let Some(field) = Field::get_all().into_iter().find(|f| f.to_string() == key) else {
+            return Err(JsonError::UnknownField { index, key });
+        };
+        let invalid = || JsonError::InvalidField {
+            index,
+            field: field.clone(),
+        };
+
+        let value = match (&field, value) {
+            (Field::TxType | Field::Status | Field::Description, Value::String(value)) => value,
+            (Field::TxType | Field::Status | Field::Description, _) => return Err(invalid()),
+            (_, Value::Number(value)) => value.to_string(),
+            _ => return Err(invalid()),
+        };
+        if field == Field::Description
+            && let Some(limit) = limits.max_description_bytes
+            && value.len() > limit
+        {
+            return Err(JsonError::DescriptionTooLong { index, limit });
+        }
+
+        builder = builder.value(&field, &value).map_err(|_| invalid())?;
+    }
+
+    builder.build().map_err(|e| match e {
+        TransactionBuildError::MissingField { field } => JsonError::MissingField { index, field },
+        TransactionBuildError::InvalidField { field } => JsonError::InvalidField { index, field },
+    })
+}
+
+/// Запись транзакции в виде json объекта в одну строку
+pub(crate) fn write_object(t: &Transaction) -> String {
+    let fields = Field::get_all()
+        .map(|field| {
+            let value = t.get_value(&field);
+            let value = match field {
+                Field::TxType | Field::Status | Field::Description => {
+                    Value::String(value).to_string()
+                }
+                _ => value,
+            };
+            format!("\"{field}\":{value}")
+        })
+        .join(",");
+    format!("{{{fields}}}")
+}
+
+impl Parser for JsonParser {
+    type Error = JsonError;
+
+    fn records_with_options<R: BufRead>(
+        reader: R,
+        options: ParseOptions,
+    ) -> impl Iterator<Item = Result<Transaction, JsonError>> {
+        JsonRecords::new(reader)
+            .with_mode(options.mode)
+            .with_limits(options.limits)
+    }
+
+    fn writer<W: Write>(writer: W) -> impl TransactionWriter {
+        JsonWriter::new(writer)
+    }
+}
+
+impl Format for JsonParser {
+    fn parse_options(&self) -> ParseOptions {
+        self.options
+    }
+
+    fn with_parse_options(&self, options: ParseOptions) -> Box<dyn Format> {
+        Box::new(Self { options })
+    }
+
+    fn read_records<'a>(
+        &self,
+        reader: &'a mut dyn BufRead,
+    ) -> Box<dyn Iterator<Item = Result<Transaction, ParserError>> + 'a> {
+        let records = JsonRecords::new(reader)
+            .with_mode(self.options.mode)
+            .with_limits(self.options.limits);
+        Box::new(records.map(|r| Ok(r?)))
+    }
+
+    fn create_writer<'a>(&self, writer: &'a mut dyn Write) -> Box<dyn TransactionWriter + 'a> {
+        Box::new(JsonWriter::new(writer))
+    }
+}
+
+/// Потоковая запись транзакций в json формате
+pub struct JsonWriter<W> {
+    writer: W,
+    count: usize,
+}
+
+impl<W: Write> JsonWriter<W> {
+    /// Создание писателя
+    pub fn new(writer: W) -> Self {
+        Self { writer, count: 0 }
+    }
+
+    /// Получение исходного потока
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> TransactionWriter for JsonWriter<W> {
+    fn write(&mut self, t: &Transaction) -> Result<(), WriteError> {
+        let separator = if self.count == 0 { "[" } else { "," };
+        self.count += 1;
+        write!(self.writer, "{separator}\n  {}", write_object(t)).map_err(WriteError::Write)
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        let end = if self.count == 0 { "[]\n" } else { "\n]\n" };
+        self.writer
+            .write_all(end.as_bytes())
+            .and_then(|_| self.writer.flush())
+            .map_err(WriteError::Write)
+    }
+}
+
+#[cfg(test)]
+mod tests_from_read {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{Status, TxType};
+
+    const RECORD: &str = r#"{"TX_ID":1,"TX_TYPE":"DEPOSIT","FROM_USER_ID":0,"TO_USER_ID":1,"AMOUNT":100,"TIMESTAMP":1633036860000,"STATUS":"SUCCESS","DESCRIPTION":"Test \"1\""}"#;
+
+    fn record(tx_id: u64) -> String {
+        RECORD.replace(r#""TX_ID":1"#, &format!(r#""TX_ID":{tx_id}"#))
+    }
+
+    #[test]
+    fn test_success_from_read() {
+        let mut cursor = Cursor::new(format!("\n[\n  {RECORD}\n]\n"));
+        let result = JsonParser::from_read(&mut cursor).unwrap();
+        assert_eq!(
+            result,
+            [Transaction {
+                tx_id: 1,
+                tx_type: TxType::Deposit,
+                from_user_id: 0,
+                to_user_id: 1,
+                amount: 100,
+                timestamp: 1633036860000,
+                status: Status::Success,
+                description: "Test \"1\"".to_string(),
+            }]
+        );
+
+        let result = JsonParser::from_read(&mut Cursor::new(" [ ] ")).unwrap();
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_error_from_read() {
+        let cases = [
+            ("{}", "Ожидался массив объектов в строке 0, столбце 0"),
+            ("[\n  1\n]", "Запись в строке 1 не является объектом"),
+            (
+                "[\n  {\"TX_ID\":1,\n  \"X\":2}\n]",
+                "Неизвестное поле X в строке 1",
+            ),
+            (
+                "[{\"TX_ID\":1}]",
+                "Отсутствует поле TX_TYPE в записи на строке 0",
+            ),
+            (
+                "[{\"TX_ID\":\"1\"}]",
+                "Ошибка парсинга поля TX_ID в строке 0",
+            ),
+            (
+                "[{\"STATUS\":\"DONE\"}]",
+                "Ошибка парсинга поля STATUS в строке 0",
+            ),
+            ("[] []", "Ожидался массив объектов в строке 0, столбце 3"),
+        ];
+        for (data, expected) in cases {
+            let result = JsonParser::from_read(&mut Cursor::new(data)).unwrap_err();
+            assert_eq!(result.to_string(), expected, "{data}");
+        }
+
+        let end = RECORD.len() + 1;
+        let cases = [
+            (
+                format!("[{RECORD}\n{RECORD}]"),
+                "Ожидался массив объектов в строке 1, столбце 0".to_string(),
+            ),
+            (
+                format!("[{RECORD}"),
+                format!("Ожидался массив объектов в строке 0, столбце {end}"),
+            ),
+        ];
+        for (data, expected) in cases {
+            let result = JsonParser::from_read(&mut Cursor::new(data)).unwrap_err();
+            assert_eq!(result.to_string(), expected);
+        }
+
+        let result = JsonParser::from_read(&mut Cursor::new("[\n  {\"TX_ID\": }\n]")).unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Некорректный JSON в строке 1, столбце 12: expected value at line 1 column 11"
+        );
+
+        let result = JsonParser::from_read(&mut Cursor::new(b"[\n \xff]")).unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Некорректная кодировка UTF-8 в строке 1, столбце 1"
+        );
+    }
+
+    #[test]
+    fn test_success_records_lenient() {
+        let data = format!("[\n{},\n{{\"TX_ID\":2}},\n{}\n]", record(1), record(3));
+        let result: Vec<_> = JsonParser::records_with_mode(Cursor::new(data), ParseMode::Lenient)
+            .map(|r| r.map(|t| t.tx_id).map_err(|e| e.to_string()))
+            .collect();
+        assert_eq!(
+            result,
+            [
+                Ok(1),
+                Err("Отсутствует поле TX_TYPE в записи на строке 2".to_string()),
+                Ok(3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_limits() {
+        let data = format!("[{},{}]", record(1), record(2));
+        let options = |limits| ParseOptions {
+            mode: ParseMode::Strict,
+            limits,
+        };
+
+        let result: Vec<_> = JsonParser::records_with_options(
+            Cursor::new(data.clone()),
+            options(ParseLimits {
+                max_records: Some(1),
+                ..Default::default()
+            }),
+        )
+        .map(|r| r.map(|t| t.tx_id).map_err(|e| e.to_string()))
+        .collect();
+        assert_eq!(
+            result,
+            [
+                Ok(1),
+                Err("Превышено максимальное количество записей 1".to_string())
+            ]
+        );
+
+        let result = JsonParser::records_with_options(
+            Cursor::new(data.clone()),
+            options(ParseLimits {
+                max_description_bytes: Some(4),
+                ..Default::default()
+            }),
+        )
+        .next()
+        .unwrap()
+        .unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Превышена максимальная длина описания 4 байт в строке 0"
+        );
+
+        let result = JsonParser::records_with_options(
+            Cursor::new(data),
+            options(ParseLimits {
+                max_input_bytes: Some(10),
+                ..Default::default()
+            }),
+        )
+        .next()
+        .unwrap()
+        .unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Превышен максимальный размер входных данных 10 байт"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_write_to {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{Status, TxType};
+
+    #[test]
+    fn test_success_write_to() {
+        let transactions = vec![
+            Transaction {
+                tx_id: 1,
+                tx_type: TxType::Deposit,
+                from_user_id: 0,
+                to_user_id: 1,
+                amount: 1000,
+                timestamp: 1633036860000,
+                status: Status::Success,
+                description: "record \"1\"".to_string(),
+            },
+            Transaction {
+                tx_id: 2,
+                tx_type: TxType::Transfer,
+                from_user_id: 1,
+                to_user_id: 2,
+                amount: 1111,
+                timestamp: -1,
+                status: Status::Failure,
+                description: "record 2".to_string(),
+            },
+        ];
+        let mut data = Vec::new();
+        JsonParser::write_to(&mut data, &transactions).unwrap();
+        assert_eq!(
+            String::from_utf8(data.clone()).unwrap(),
+            [
+                "[",
+                r#"  {"TX_ID":1,"TX_TYPE":"DEPOSIT","FROM_USER_ID":0,"TO_USER_ID":1,"AMOUNT":1000,"TIMESTAMP":1633036860000,"STATUS":"SUCCESS","DESCRIPTION":"record \"1\""},"#,
+                r#"  {"TX_ID":2,"TX_TYPE":"TRANSFER","FROM_USER_ID":1,"TO_USER_ID":2,"AMOUNT":1111,"TIMESTAMP":-1,"STATUS":"FAILURE","DESCRIPTION":"record 2"}"#,
+                "]\n",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            JsonParser::from_read(&mut Cursor::new(data)).unwrap(),
+            transactions
+        );
+    }
+
+    #[test]
+    fn test_success_writer_empty() {
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.finish().unwrap();
+        let data = writer.into_inner();
+        assert_eq!(data, b"[]\n");
+        assert!(
+            JsonParser::from_read(&mut Cursor::new(data))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,2 @@
+/// Модуль парсера
+pub mod parser;
@@ -0,0 +1,335 @@
+use std::io::{BufRead, Write};
+
+use serde_json::Value;
+
+use crate::{
+    Format, ParseLimits, ParseMode, ParseOptions, Parser, Transaction, TransactionWriter,
+    errors::{ParserError, WriteError},
+    parsers::{
+        json::{
+            error::JsonError,
+            parser::{parse_object, write_object},
+        },
+        utils::{LineError, LineReader, sniff_text, trim_line_end},
+    },
+};
+
+/// Парсер для jsonl формата: один объект на строку
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct JsonlParser {
+    options: ParseOptions,
+}
+
+impl JsonlParser {
+    /// Создание парсера с настройками по умолчанию
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Установка режима разбора
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.options.mode = mode;
+        self
+    }
+
+    /// Установка ограничений на размер входных данных
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.options.limits = limits;
+        self
+    }
+}
+
+/// Потоковое чтение записей jsonl формата
+pub struct JsonlRecords<R> {
+    reader: R,
+    mode: ParseMode,
+    limits: ParseLimits,
+    lines: LineReader,
+    line: Vec<u8>,
+    index: usize,
+    count: usize,
+    finished: bool,
+}
+
+impl<R: BufRead> JsonlRecords<R> {
+    /// Создание итератора по записям
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            mode: ParseMode::Strict,
+            limits: ParseLimits::default(),
+            lines: LineReader::new(ParseLimits::default()),
+            line: Vec::new(),
+            index: 0,
+            count: 0,
+            finished: false,
+        }
+    }
+
+    /// Установка режима разбора
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Установка ограничений на размер входных данных
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self.lines = LineReader::new(limits);
+        self
+    }
+
+    fn read_line(&mut self, index: usize) -> Result<bool, JsonError> {
+        self.lines.reset();
+        match self.lines.read(&mut self.reader, &mut self.line) {
+            Ok(has_line) => Ok(has_line),
+            Err(LineError::Io(source)) => Err(JsonError::Read { index, source }),
+            Err(LineError::LineTooLong(limit)) => {
+                self.index += 1;
+                Err(JsonError::LineTooLong { index, limit })
+            }
+            Err(LineError::InputTooLarge(limit)) => Err(JsonError::InputTooLarge { limit }),
+        }
+    }
+
+    fn read_record(&mut self) -> Result<Option<Transaction>, JsonError> {
+        loop {
+            let index = self.index;
+
+            if !self.read_line(index)? {
+                return Ok(None);
+            }
+
+            self.index += 1;
+
+            let line =
+                str::from_utf8(trim_line_end(&self.line)).map_err(|source| JsonError::Utf8 {
+                    index,
+                    column: source.valid_up_to(),
+                    source,
+                })?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let value =
+                serde_json::from_str::<Value>(line).map_err(|source| JsonError::Syntax {
+                    index,
+                    column: source.column().saturating_sub(1),
+                    source,
+                })?;
+            return parse_object(value, index, &self.limits).map(Some);
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for JsonlRecords<R> {
+    type Item = Result<Transaction, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let mut result = self.read_record().transpose();
+        if result.is_some()
+            && let Some(limit) = self.limits.max_records
+            && self.count >= limit
+        {
+            result = Some(Err(JsonError::TooManyRecords { limit }));
+        }
+        self.count += 1;
+        self.finished = match &result {
+            None => true,
+            Some(Ok(_)) => false,
+            Some(Err(e)) => self.mode == ParseMode::Strict || !is_recoverable(e),
+        };
+        result
+    }
+}
+
+/// Ошибки, после которых можно продолжить чтение со следующей строки
+fn is_recoverable(error: &JsonError) -> bool {
+    matches!(
+        error,
+        JsonError::Utf8 { .. }
+            | JsonError::Syntax { .. }
+            | JsonError::NotObject { .. }
+            | JsonError::UnknownField { .. }
+            | JsonError::MissingField { .. }
+            | JsonError::InvalidField { .. }
+            | JsonError::LineTooLong { .. }
+            | JsonError::DescriptionTooLong { .. }
+    )
+}
+
+/// Файл начинается с объекта
+pub(crate) fn sniff(data: &[u8]) -> bool {
+    sniff_text(data).trim_start().starts_with('{')
+}
+
+impl Parser for JsonlParser {
+    type Error = JsonError;
+
+    fn records_with_options<R: BufRead>(
+        reader: R,
+        options: ParseOptions,
+    ) -> impl Iterator<Item = Result<Transaction, JsonError>> {
+        JsonlRecords::new(reader)
+            .with_mode(options.mode)
+            .with_limits(options.limits)
+    }
+
+    fn writer<W: Write>(writer: W) -> impl TransactionWriter {
+        JsonlWriter::new(writer)
+    }
+}
+
+impl Format for JsonlParser {
+    fn parse_options(&self) -> ParseOptions {
+        self.options
+    }
+
+    fn with_parse_options(&self, options: ParseOptions) -> Box<dyn Format> {
+        Box::new(Self { options })
+    }
+
+    fn read_records<'a>(
+        &self,
+        reader: &'a mut dyn BufRead,
+    ) -> Box<dyn Iterator<Item = Result<Transaction, ParserError>> + 'a> {
+        let records = JsonlRecords::new(reader)
+            .with_mode(self.options.mode)
+            .with_limits(self.options.limits);
+        Box::new(records.map(|r| Ok(r?)))
+    }
+
+    fn create_writer<'a>(&self, writer: &'a mut dyn Write) -> Box<dyn TransactionWriter + 'a> {
+        Box::new(JsonlWriter::new(writer))
+    }
+}
+
+/// Потоковая запись транзакций в jsonl формате
+pub struct JsonlWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> JsonlWriter<W> {
+    /// Создание писателя
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Получение исходного потока
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> TransactionWriter for JsonlWriter<W> {
+    fn write(&mut self, t: &Transaction) -> Result<(), WriteError> {
+        writeln!(self.writer, "{}", write_object(t)).map_err(WriteError::Write)
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        self.writer.flush().map_err(WriteError::Write)
+    }
+}
+
+#[cfg(test)]
+mod tests_from_read {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn record(tx_id: u64) -> String {
+        format!(
+            r#"{{"TX_ID":{tx_id},"TX_TYPE":"WITHDRAWAL","FROM_USER_ID":1,"TO_USER_ID":0,"AMOUNT":5,"TIMESTAMP":1633036860000,"STATUS":"PENDING","DESCRIPTION":"record {tx_id}"}}"#
+        )
+    }
+
+    #[test]
+    fn test_success_records_lenient() {
+        let data = [
+            record(1),
+            String::new(),
+            "{\"TX_ID\":2,".to_string(),
+            record(3).replace("AMOUNT\":5", "AMOUNT\":-5"),
+            "[]".to_string(),
+            record(5).replace("\"TX_ID\"", "\"ID\""),
+            record(6),
+        ]
+        .join("\r\n");
+        let result: Vec<_> = JsonlParser::records_with_mode(Cursor::new(data), ParseMode::Lenient)
+            .map(|r| r.map(|t| t.tx_id).map_err(|e| e.to_string()))
+            .collect();
+        assert_eq!(
+            result,
+            [
+                Ok(1),
+                Err("Некорректный JSON в строке 2, столбце 10: EOF while parsing a value at line 1 column 11".to_string()),
+                Err("Ошибка парсинга поля AMOUNT в строке 3".to_string()),
+                Err("Запись в строке 4 не является объектом".to_string()),
+                Err("Неизвестное поле ID в строке 5".to_string()),
+                Ok(6),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_limits() {
+        let data = [record(1), record(2)].join("\n");
+        let result = JsonlParser::records_with_options(
+            Cursor::new(data),
+            ParseOptions {
+                mode: ParseMode::Lenient,
+                limits: ParseLimits {
+                    max_line_length: Some(10),
+                    ..Default::default()
+                },
+            },
+        )
+        .map(|r| r.map_err(|e| e.to_string()).unwrap_err())
+        .collect::<Vec<_>>();
+        assert_eq!(
+            result,
+            [
+                "Превышена максимальная длина строки 10 байт в строке 0",
+                "Превышена максимальная длина строки 10 байт в строке 1",
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests_write_to {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_success_write_to() {
+        let transactions: Vec<Transaction> = (1..=2)
+            .map(|tx_id| Transaction {
+                tx_id,
+                description: format!("record\n{tx_id}"),
+                ..Default::default()
+            })
+            .collect();
+        let mut data = Vec::new();
+        JsonlParser::write_to(&mut data, &transactions).unwrap();
+        assert_eq!(
+            String::from_utf8(data.clone()).unwrap(),
+            [
+                r#"{"TX_ID":1,"TX_TYPE":"DEPOSIT","FROM_USER_ID":0,"TO_USER_ID":0,"AMOUNT":0,"TIMESTAMP":0,"STATUS":"SUCCESS","DESCRIPTION":"record\n1"}"#,
+                r#"{"TX_ID":2,"TX_TYPE":"DEPOSIT","FROM_USER_ID":0,"TO_USER_ID":0,"AMOUNT":0,"TIMESTAMP":0,"STATUS":"SUCCESS","DESCRIPTION":"record\n2"}"#,
+                "",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            JsonlParser::from_read(&mut Cursor::new(data)).unwrap(),
+            transactions
+        );
+    }
+}
@@ -4,5 +4,9 @@ mod utils;
 pub mod bin;
 /// Модуль csv парсера
 pub mod csv;
+/// Модуль json парсера
+pub mod json;
+/// Модуль jsonl парсера
+pub mod jsonl;
 /// Модуль txt парсера
 pub mod txt;
@@ -1,3 +1,5 @@
+use std::{io, str::Utf8Error};
+
 use thiserror::Error;
 
 use crate::Field;
@@ -9,8 +11,25 @@ pub enum TxtError {
     #[error("Неизвестная ошибка")]
     Unknown,
     /// Ошибка чтения
-    #[error("Ошибка чтения")]
-    Read,
+    #[error("Ошибка чтения в строке {index}: {source}")]
+    Read {
+        /// Индекс строки
+        index: usize,
+        /// Исходная ошибка ввода-вывода
+        #[source]
+        source: io::Error,
+    },
+    /// Строка не является корректной UTF-8 последовательностью
+    #[error("Некорректная кодировка UTF-8 в строке {index}, столбце {column}")]
+    Utf8 {
+        /// Индекс строки
+        index: usize,
+        /// Смещение в строке в байтах
+        column: usize,
+        /// Исходная ошибка декодирования
+        #[source]
+        source: Utf8Error,
+    },
     /// Некорректный формат в строке
     #[error("Некорректный формат в строке {index}")]
     LineFormat {
@@ -40,11 +59,53 @@ pub enum TxtError {
         field: Field,
     },
     /// Некорректное поле
-    #[error("Ошибка парсинга поля {field} в строке {index}")]
+    #[error("Ошибка парсинга поля {field} в строке {index}, столбце {column}")]
     InvalidField {
         /// Индекс строки
         index: usize,
+        /// Смещение начала значения в строке в байтах
+        column: usize,
         /// Поле
         field: Field,
     },
+    /// Превышена максимальная длина строки
+    #[error("Превышена максимальная длина строки {limit} байт в строке {index}")]
+    LineTooLong {
+        /// Индекс строки
+        index: usize,
+        /// Ограничение
+        limit: usize,
+    },
+    /// Превышен максимальный размер входных данных
+    #[error("Превышен максимальный размер входных данных {limit} байт")]
+    InputTooLarge {
+        /// Ограничение
+        limit: u64,
+    },
+    /// Превышено максимальное количество записей
+    #[error("Превышено максимальное количество записей {limit}")]
+    TooManyRecords {
+        /// Ограничение
+        limit: usize,
+    },
+    /// Превышена максимальная длина описания
+    #[error(
+        "Превышена максимальная длина описания {limit} байт в строке {index}, столбце {column}"
+    )]
+    DescriptionTooLong {
+        /// Индекс строки
+        index: usize,
+        /// Смещение начала поля в строке в байтах
+        column: usize,
+        /// Ограничение
+        limit: usize,
+    },
+    /// Незавершённая escape-последовательность в описании
+    #[error("Незавершённая escape-последовательность в строке {index}, столбце {column}")]
+    InvalidEscape {
+        /// Индекс строки
+        index: usize,
+        /// Смещение символа `\` в строке в байтах
+        column: usize,
+    },
 }
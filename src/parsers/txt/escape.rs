@@ -0,0 +1,103 @@
+use crate::{Field, parsers::txt::error::TxtError};
+
+/// Экранирование описания: значение заключается в кавычки,
+/// а `\`, `"`, перевод строки, возврат каретки и табуляция записываются через `\`
+pub fn quote(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for c in value.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '"' => result.push_str("\\\""),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            _ => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Разбор описания, записанного через [`quote`].
+/// Неизвестные escape-последовательности, например `\d` в `"C:\dir"`, сохраняются как есть.
+/// `index` и `column` - номер строки и смещение значения в ней для сообщений об ошибках
+pub fn unquote(value: &str, index: usize, column: usize) -> Result<String, TxtError> {
+    let inner = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or(TxtError::InvalidField {
+            index,
+            column,
+            field: Field::Description,
+        })?;
+
+    let mut result = String::with_capacity(inner.len());
+    let mut chars = inner.char_indices();
+    while let Some((position, c)) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        let Some((_, next)) = chars.next() else {
+            // `\` перед закрывающей кавычкой экранирует её, и значение не завершено
+            return Err(TxtError::InvalidEscape {
+                index,
+                column: column + 1 + position,
+            });
+        };
+        match next {
+            '\\' => result.push('\\'),
+            '"' => result.push('"'),
+            'n' => result.push('\n'),
+            'r' => result.push('\r'),
+            't' => result.push('\t'),
+            _ => {
+                result.push('\\');
+                result.push(next);
+            }
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(
+            quote("Invoice: \"March\"\n\\ end"),
+            "\"Invoice: \\\"March\\\"\\n\\\\ end\""
+        );
+    }
+
+    #[test]
+    fn test_success_unquote() {
+        for value in ["", "plain", "Invoice: March", "\"\\\n\r\t", "\\n"] {
+            assert_eq!(unquote(&quote(value), 0, 0).unwrap(), value);
+        }
+
+        // неизвестные последовательности сохраняются как есть
+        assert_eq!(unquote("\"C:\\dir\\x\"", 0, 0).unwrap(), "C:\\dir\\x");
+        assert_eq!(unquote("\"\\é\"", 0, 0).unwrap(), "\\é");
+    }
+
+    #[test]
+    fn test_error_unquote() {
+        let error = |value: &str| unquote(value, 3, 13).unwrap_err().to_string();
+        assert_eq!(
+            error("no quotes"),
+            "Ошибка парсинга поля DESCRIPTION в строке 3, столбце 13"
+        );
+        assert_eq!(
+            error("\""),
+            "Ошибка парсинга поля DESCRIPTION в строке 3, столбце 13"
+        );
+        assert_eq!(
+            error("\"trailing \\\""),
+            "Незавершённая escape-последовательность в строке 3, столбце 23"
+        );
+    }
+}
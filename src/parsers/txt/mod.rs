@@ -1,4 +1,5 @@
 /// Модуль ошибок
 pub mod error;
+mod escape;
 /// Модуль парсера
 pub mod parser;
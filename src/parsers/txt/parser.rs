@@ -1,59 +1,157 @@
-use std::io::{Read, Write};
+use std::io::{BufRead, Write};
 
 use crate::{
-    Field, Parser, Status, Transaction, TxType,
-    errors::WriteError,
-    parsers::{txt::error::TxtError, utils::description_trim},
+    Field, Format, ParseLimits, ParseMode, ParseOptions, Parser, Transaction, TransactionWriter,
+    builder::TransactionBuilder,
+    errors::{ParserError, TransactionBuildError, WriteError},
+    parsers::{
+        txt::{
+            error::TxtError,
+            escape::{quote, unquote},
+        },
+        utils::{LineError, LineReader, sniff_text, trim_line_end},
+    },
 };
 
 /// Парсер для txt формата
-pub struct TxtParser;
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TxtParser {
+    options: ParseOptions,
+}
 
-impl Parser for TxtParser {
-    type Error = TxtError;
+impl TxtParser {
+    /// Создание парсера с настройками по умолчанию
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    fn from_read<R: Read>(reader: &mut R) -> Result<Vec<Transaction>, TxtError> {
-        let mut content = String::new();
-        reader
-            .read_to_string(&mut content)
-            .map_err(|_| TxtError::Read)?;
-
-        let mut transactions: Vec<Transaction> = Vec::new();
-        let mut transaction = Transaction::default();
-
-        let mut parsed_fields = Field::get_all().map(|f| (f, false));
-
-        let mut lines: Vec<&str> = content.lines().collect();
-        lines.push(""); // добавляем пустую строку для обработки последней записи
-        let last_index = lines.len() - 1;
-
-        for (index, line) in lines.iter().enumerate() {
-            if index == last_index || line.is_empty() {
-                let missed_field = parsed_fields.iter().find(|f| !f.1);
-                if let Some((field, _)) = missed_field {
-                    if line.is_empty() && parsed_fields.iter().all(|f| !f.1) {
-                        continue;
-                    }
-                    return Err(TxtError::MissingField {
-                        index,
-                        field: field.clone(),
-                    });
+    /// Установка режима разбора
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.options.mode = mode;
+        self
+    }
+
+    /// Установка ограничений на размер входных данных
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.options.limits = limits;
+        self
+    }
+}
+
+/// Потоковое чтение записей txt формата
+pub struct TxtRecords<R> {
+    reader: R,
+    mode: ParseMode,
+    limits: ParseLimits,
+    lines: LineReader,
+    line: Vec<u8>,
+    index: usize,
+    count: usize,
+    skip_record: bool,
+    finished: bool,
+}
+
+impl<R: BufRead> TxtRecords<R> {
+    /// Создание итератора по записям
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            mode: ParseMode::Strict,
+            limits: ParseLimits::default(),
+            lines: LineReader::new(ParseLimits::default()),
+            line: Vec::new(),
+            index: 0,
+            count: 0,
+            skip_record: false,
+            finished: false,
+        }
+    }
+
+    /// Установка режима разбора
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Установка ограничений на размер входных данных
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self.lines = LineReader::new(limits);
+        self
+    }
+
+    /// Чтение строки без символов перевода строки
+    fn read_line(&mut self) -> Result<bool, TxtError> {
+        let index = self.index;
+        self.lines.reset();
+        match self.lines.read(&mut self.reader, &mut self.line) {
+            Ok(has_line) => {
+                let length = trim_line_end(&self.line).len();
+                self.line.truncate(length);
+                Ok(has_line)
+            }
+            Err(LineError::Io(source)) => Err(TxtError::Read { index, source }),
+            Err(LineError::LineTooLong(limit)) => {
+                self.index += 1;
+                Err(TxtError::LineTooLong { index, limit })
+            }
+            Err(LineError::InputTooLarge(limit)) => Err(TxtError::InputTooLarge { limit }),
+        }
+    }
+
+    /// Пропуск оставшихся строк некорректной записи до пустой строки
+    fn skip_record(&mut self) -> Result<(), TxtError> {
+        while self.skip_record {
+            match self.read_line() {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(TxtError::LineTooLong { .. }) => continue,
+                Err(e) => return Err(e),
+            }
+            self.index += 1;
+            if self.line.is_empty() {
+                break;
+            }
+        }
+        self.skip_record = false;
+        Ok(())
+    }
+
+    fn read_record(&mut self) -> Result<Option<Transaction>, TxtError> {
+        self.skip_record()?;
+
+        let mut builder = TransactionBuilder::new();
+
+        loop {
+            let index = self.index;
+
+            if !self.read_line()? {
+                if builder == TransactionBuilder::default() {
+                    return Ok(None);
                 }
-                parsed_fields.iter_mut().for_each(|f| {
-                    f.1 = false;
-                });
-                transactions.push(transaction);
-                transaction = Transaction::default();
-                continue;
+                return build_record(builder, index).map(Some);
+            }
+
+            self.index += 1;
+
+            let line = str::from_utf8(&self.line).map_err(|source| TxtError::Utf8 {
+                index,
+                column: source.valid_up_to(),
+                source,
+            })?;
+
+            if line.is_empty() {
+                if builder == TransactionBuilder::default() {
+                    continue;
+                }
+                return build_record(builder, index).map(Some);
             }
 
             if line.starts_with("#") {
                 continue;
             }
 
-            let key_and_value: Vec<&str> = line.split(": ").collect();
-
-            let [key, value] = key_and_value[..] else {
+            let Some((key, value)) = line.split_once(": ") else {
                 return Err(TxtError::LineFormat { index });
             };
 
@@ -62,87 +160,186 @@ impl Parser for TxtParser {
                 .find(|f| f.to_string() == key)
                 .ok_or(TxtError::UnknownField { index })?;
 
-            let parsed_field = parsed_fields
-                .iter_mut()
-                .find(|f| f.0 == field)
-                .ok_or(TxtError::Unknown)?;
-            if parsed_field.1 {
+            if builder.is_set(&field) {
                 return Err(TxtError::FieldAlreadyExists { index, field });
             }
-            parsed_field.1 = true;
 
-            let parse_col_u64 = |field: Field| {
-                value
-                    .parse::<u64>()
-                    .map_err(|_| TxtError::InvalidField { index, field })
-            };
-            let parse_col_i64 = |field: Field| {
+            let column = key.len() + 2;
+
+            let value = if field == Field::Description {
+                let value = unquote(value, index, column)?;
+                if let Some(limit) = self.limits.max_description_bytes
+                    && value.len() > limit
+                {
+                    return Err(TxtError::DescriptionTooLong {
+                        index,
+                        column,
+                        limit,
+                    });
+                }
                 value
-                    .parse::<i64>()
-                    .map_err(|_| TxtError::InvalidField { index, field })
+            } else {
+                value.to_string()
             };
 
-            match field {
-                Field::TxId => {
-                    transaction.tx_id = parse_col_u64(field)?;
-                }
-                Field::TxType => {
-                    transaction.tx_type = value
-                        .parse::<TxType>()
-                        .map_err(|_| TxtError::InvalidField { index, field })?;
-                }
-                Field::FromUserId => {
-                    transaction.from_user_id = parse_col_u64(field)?;
-                }
-                Field::ToUserId => {
-                    transaction.to_user_id = parse_col_u64(field)?;
-                }
-                Field::Amount => {
-                    transaction.amount = parse_col_u64(field)?;
-                }
-                Field::Timestamp => {
-                    transaction.timestamp = parse_col_i64(field)?;
-                }
-                Field::Status => {
-                    transaction.status = value
-                        .parse::<Status>()
-                        .map_err(|_| TxtError::InvalidField { index, field })?;
-                }
-                Field::Description => {
-                    transaction.description = description_trim(value)
-                        .map_err(|_| TxtError::InvalidField { index, field })?;
-                }
-            }
+            builder = builder
+                .value(&field, &value)
+                .map_err(|_| TxtError::InvalidField {
+                    index,
+                    column,
+                    field,
+                })?;
         }
+    }
+}
+
+/// Первая значимая строка файла имеет вид `FIELD: value`
+pub(crate) fn sniff(data: &[u8]) -> bool {
+    let text = sniff_text(data);
+    let first_line = text
+        .lines()
+        .find(|line| !line.is_empty() && !line.starts_with("#"));
+    first_line.is_some_and(|line| {
+        Field::get_all()
+            .iter()
+            .any(|f| line.starts_with(&format!("{f}: ")))
+    })
+}
+
+fn build_record(builder: TransactionBuilder, index: usize) -> Result<Transaction, TxtError> {
+    builder.build().map_err(|e| match e {
+        TransactionBuildError::MissingField { field } => TxtError::MissingField { index, field },
+        TransactionBuildError::InvalidField { .. } => TxtError::Unknown,
+    })
+}
+
+impl<R: BufRead> Iterator for TxtRecords<R> {
+    type Item = Result<Transaction, TxtError>;
 
-        Ok(transactions)
-    }
-
-    fn write_to<W: Write>(writer: &mut W, transactions: &[Transaction]) -> Result<(), WriteError> {
-        for t in transactions {
-            let transtaction_record = Field::get_all()
-                .map(|field| {
-                    let value = t.get_value(&field);
-                    let value = if field == Field::Description {
-                        format!("\"{value}\"")
-                    } else {
-                        value
-                    };
-                    format!("{}: {}", field, value)
-                })
-                .join("\n");
-            write!(writer, "{transtaction_record}\n\n").map_err(|_| WriteError::Write)?;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
         }
-        writer.flush().map_err(|_| WriteError::Write)?;
-        Ok(())
+        let mut result = self.read_record().transpose();
+        if result.is_some()
+            && let Some(limit) = self.limits.max_records
+            && self.count >= limit
+        {
+            result = Some(Err(TxtError::TooManyRecords { limit }));
+        }
+        self.count += 1;
+        match &result {
+            None => self.finished = true,
+            Some(Ok(_)) => {}
+            Some(Err(e)) => {
+                self.finished = self.mode == ParseMode::Strict || !is_recoverable(e);
+                self.skip_record = !matches!(e, TxtError::MissingField { .. });
+            }
+        }
+        result
+    }
+}
+
+/// Ошибки, после которых можно продолжить чтение со следующей записи
+fn is_recoverable(error: &TxtError) -> bool {
+    matches!(
+        error,
+        TxtError::Utf8 { .. }
+            | TxtError::LineFormat { .. }
+            | TxtError::UnknownField { .. }
+            | TxtError::FieldAlreadyExists { .. }
+            | TxtError::MissingField { .. }
+            | TxtError::InvalidField { .. }
+            | TxtError::LineTooLong { .. }
+            | TxtError::DescriptionTooLong { .. }
+            | TxtError::InvalidEscape { .. }
+    )
+}
+
+impl Parser for TxtParser {
+    type Error = TxtError;
+
+    fn records_with_options<R: BufRead>(
+        reader: R,
+        options: ParseOptions,
+    ) -> impl Iterator<Item = Result<Transaction, TxtError>> {
+        TxtRecords::new(reader)
+            .with_mode(options.mode)
+            .with_limits(options.limits)
+    }
+
+    fn writer<W: Write>(writer: W) -> impl TransactionWriter {
+        TxtWriter::new(writer)
+    }
+}
+
+impl Format for TxtParser {
+    fn parse_options(&self) -> ParseOptions {
+        self.options
+    }
+
+    fn with_parse_options(&self, options: ParseOptions) -> Box<dyn Format> {
+        Box::new(Self { options })
+    }
+
+    fn read_records<'a>(
+        &self,
+        reader: &'a mut dyn BufRead,
+    ) -> Box<dyn Iterator<Item = Result<Transaction, ParserError>> + 'a> {
+        let records = TxtRecords::new(reader)
+            .with_mode(self.options.mode)
+            .with_limits(self.options.limits);
+        Box::new(records.map(|r| Ok(r?)))
+    }
+
+    fn create_writer<'a>(&self, writer: &'a mut dyn Write) -> Box<dyn TransactionWriter + 'a> {
+        Box::new(TxtWriter::new(writer))
+    }
+}
+
+/// Потоковая запись транзакций в txt формате
+pub struct TxtWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> TxtWriter<W> {
+    /// Создание писателя
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Получение исходного потока
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> TransactionWriter for TxtWriter<W> {
+    fn write(&mut self, t: &Transaction) -> Result<(), WriteError> {
+        let transtaction_record = Field::get_all()
+            .map(|field| {
+                let value = if field == Field::Description {
+                    quote(&t.description)
+                } else {
+                    t.get_value(&field)
+                };
+                format!("{}: {}", field, value)
+            })
+            .join("\n");
+        write!(self.writer, "{transtaction_record}\n\n").map_err(WriteError::Write)
+    }
+
+    fn finish(&mut self) -> Result<(), WriteError> {
+        self.writer.flush().map_err(WriteError::Write)
     }
 }
 
 #[cfg(test)]
 mod tests_from_read {
-    use std::io::Cursor;
+    use std::io::{BufReader, Cursor};
 
     use super::*;
+    use crate::{Status, TxType};
 
     fn get_cursor(lines: Vec<&str>) -> Cursor<String> {
         let data = lines.join("\n");
@@ -177,6 +374,125 @@ mod tests_from_read {
         );
     }
 
+    #[test]
+    fn test_success_records() {
+        let cursor = get_cursor(vec![
+            "# comment",
+            "TX_ID: 1",
+            "TX_TYPE: DEPOSIT",
+            "FROM_USER_ID: 0",
+            "TO_USER_ID: 1",
+            "AMOUNT: 100",
+            "TIMESTAMP: 1633036860000",
+            "STATUS: SUCCESS",
+            "DESCRIPTION: \"Test 1\"",
+            "",
+            "",
+            "TX_ID: 2",
+            "",
+            "TX_ID: 3",
+        ]);
+        let mut records = TxtParser::records(BufReader::new(cursor));
+        assert_eq!(records.next().unwrap().unwrap().tx_id, 1);
+        assert_eq!(
+            records.next().unwrap().unwrap_err().to_string(),
+            "Отсутствует поле TX_TYPE в записи на строке 12"
+        );
+        assert!(records.next().is_none());
+    }
+
+    #[test]
+    fn test_success_records_lenient() {
+        let record = |tx_id: &str, amount: &str| {
+            format!(
+                "TX_ID: {tx_id}\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 1\nAMOUNT: {amount}\nTIMESTAMP: 1633036860000\nSTATUS: SUCCESS\nDESCRIPTION: \"Test\"\n"
+            )
+        };
+        let data = [
+            record("1", "100"),
+            record("2", "!"),
+            "TX_ID: 3\n".to_string(),
+            record("4", "100"),
+        ]
+        .join("\n");
+        let result: Vec<_> =
+            TxtParser::records_with_mode(BufReader::new(Cursor::new(data)), ParseMode::Lenient)
+                .map(|r| r.map(|t| t.tx_id).map_err(|e| e.to_string()))
+                .collect();
+        assert_eq!(
+            result,
+            [
+                Ok(1),
+                Err("Ошибка парсинга поля AMOUNT в строке 13, столбце 8".to_string()),
+                Err("Отсутствует поле TX_TYPE в записи на строке 19".to_string()),
+                Ok(4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_limits() {
+        let record = |tx_id: &str, description: &str| {
+            format!(
+                "TX_ID: {tx_id}\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 1\nAMOUNT: 100\nTIMESTAMP: 1633036860000\nSTATUS: SUCCESS\nDESCRIPTION: \"{description}\"\n"
+            )
+        };
+        let data = [
+            record("1", &"a".repeat(20)),
+            record("2", &"b".repeat(1000)),
+            record("3", "Test"),
+            record("4", "Test"),
+        ]
+        .join("\n");
+        let options = ParseOptions {
+            mode: ParseMode::Lenient,
+            limits: ParseLimits {
+                max_description_bytes: Some(10),
+                max_records: Some(3),
+                max_line_length: Some(100),
+                ..Default::default()
+            },
+        };
+        let result: Vec<_> =
+            TxtParser::records_with_options(BufReader::new(Cursor::new(data)), options)
+                .map(|r| r.map(|t| t.tx_id).map_err(|e| e.to_string()))
+                .collect();
+        assert_eq!(
+            result,
+            [
+                Err(
+                    "Превышена максимальная длина описания 10 байт в строке 7, столбце 13"
+                        .to_string()
+                ),
+                Err("Превышена максимальная длина строки 100 байт в строке 16".to_string()),
+                Ok(3),
+                Err("Превышено максимальное количество записей 3".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_input_too_large() {
+        let data = "# comment\n".repeat(100);
+        let options = ParseOptions {
+            mode: ParseMode::Lenient,
+            limits: ParseLimits {
+                max_input_bytes: Some(100),
+                ..Default::default()
+            },
+        };
+        let result: Vec<_> =
+            TxtParser::records_with_options(BufReader::new(Cursor::new(data)), options)
+                .map(|r| r.map_err(|e| e.to_string()))
+                .collect();
+        assert_eq!(
+            result,
+            [Err(
+                "Превышен максимальный размер входных данных 100 байт".to_string()
+            )]
+        );
+    }
+
     #[test]
     fn test_error_line_format() {
         let mut cursor = get_cursor(vec!["Test"]);
@@ -229,7 +545,10 @@ mod tests_from_read {
             "DESCRIPTION: \"Test 1\"",
         ]);
         let result = TxtParser::from_read(&mut cursor).unwrap_err();
-        assert_eq!(result.to_string(), "Ошибка парсинга поля TX_ID в строке 0");
+        assert_eq!(
+            result.to_string(),
+            "Ошибка парсинга поля TX_ID в строке 0, столбце 7"
+        );
 
         let mut cursor = get_cursor(vec![
             "TX_ID: 0",
@@ -244,7 +563,7 @@ mod tests_from_read {
         let result = TxtParser::from_read(&mut cursor).unwrap_err();
         assert_eq!(
             result.to_string(),
-            "Ошибка парсинга поля TX_TYPE в строке 1"
+            "Ошибка парсинга поля TX_TYPE в строке 1, столбце 9"
         );
 
         let mut cursor = get_cursor(vec![
@@ -260,7 +579,7 @@ mod tests_from_read {
         let result = TxtParser::from_read(&mut cursor).unwrap_err();
         assert_eq!(
             result.to_string(),
-            "Ошибка парсинга поля FROM_USER_ID в строке 2"
+            "Ошибка парсинга поля FROM_USER_ID в строке 2, столбце 14"
         );
 
         let mut cursor = get_cursor(vec![
@@ -276,7 +595,7 @@ mod tests_from_read {
         let result = TxtParser::from_read(&mut cursor).unwrap_err();
         assert_eq!(
             result.to_string(),
-            "Ошибка парсинга поля TO_USER_ID в строке 3"
+            "Ошибка парсинга поля TO_USER_ID в строке 3, столбце 12"
         );
 
         let mut cursor = get_cursor(vec![
@@ -290,7 +609,10 @@ mod tests_from_read {
             "DESCRIPTION: \"Test 1\"",
         ]);
         let result = TxtParser::from_read(&mut cursor).unwrap_err();
-        assert_eq!(result.to_string(), "Ошибка парсинга поля AMOUNT в строке 4");
+        assert_eq!(
+            result.to_string(),
+            "Ошибка парсинга поля AMOUNT в строке 4, столбце 8"
+        );
 
         let mut cursor = get_cursor(vec![
             "TX_ID: 0",
@@ -305,7 +627,7 @@ mod tests_from_read {
         let result = TxtParser::from_read(&mut cursor).unwrap_err();
         assert_eq!(
             result.to_string(),
-            "Ошибка парсинга поля TIMESTAMP в строке 5"
+            "Ошибка парсинга поля TIMESTAMP в строке 5, столбце 11"
         );
 
         let mut cursor = get_cursor(vec![
@@ -319,7 +641,10 @@ mod tests_from_read {
             "DESCRIPTION: \"Test 1\"",
         ]);
         let result = TxtParser::from_read(&mut cursor).unwrap_err();
-        assert_eq!(result.to_string(), "Ошибка парсинга поля STATUS в строке 6");
+        assert_eq!(
+            result.to_string(),
+            "Ошибка парсинга поля STATUS в строке 6, столбце 8"
+        );
 
         let mut cursor = get_cursor(vec![
             "TX_ID: 0",
@@ -334,16 +659,42 @@ mod tests_from_read {
         let result = TxtParser::from_read(&mut cursor).unwrap_err();
         assert_eq!(
             result.to_string(),
-            "Ошибка парсинга поля DESCRIPTION в строке 7"
+            "Ошибка парсинга поля DESCRIPTION в строке 7, столбце 13"
+        );
+    }
+
+    #[test]
+    fn test_description_escapes() {
+        let record = |description: &'static str| {
+            get_cursor(vec![
+                "TX_ID: 0",
+                "TX_TYPE: DEPOSIT",
+                "FROM_USER_ID: 0",
+                "TO_USER_ID: 1",
+                "AMOUNT: 100",
+                "TIMESTAMP: 1633036860000",
+                "STATUS: SUCCESS",
+                description,
+            ])
+        };
+
+        let result = TxtParser::from_read(&mut record("DESCRIPTION: \"C:\\dir\\n\"")).unwrap();
+        assert_eq!(result[0].description, "C:\\dir\n");
+
+        let result = TxtParser::from_read(&mut record("DESCRIPTION: \"C:\\dir\\\"")).unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Незавершённая escape-последовательность в строке 7, столбце 20"
         );
     }
 }
 
 #[cfg(test)]
 mod tests_write_to {
-    use std::io::Cursor;
+    use std::io::{Cursor, Read};
 
     use super::*;
+    use crate::{Status, TxType};
 
     #[test]
     fn test_success_write_to() {
@@ -401,3 +752,33 @@ mod tests_write_to {
         );
     }
 }
+
+#[cfg(test)]
+mod tests_round_trip {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_success_round_trip() {
+        let transactions: Vec<Transaction> = [
+            "Invoice: March",
+            "\"quoted\" with \\ backslash",
+            "multi\nline\r\n\ttext",
+            "",
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(tx_id, description)| Transaction {
+            tx_id: tx_id as u64,
+            description: description.to_string(),
+            ..Default::default()
+        })
+        .collect();
+
+        let mut data = Vec::new();
+        TxtParser::write_to(&mut data, &transactions).unwrap();
+        let result = TxtParser::from_read(&mut Cursor::new(data)).unwrap();
+        assert_eq!(result, transactions);
+    }
+}
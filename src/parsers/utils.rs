@@ -1,7 +1,100 @@
-pub fn description_trim(value: &str) -> Result<String, ()> {
-    if value.starts_with('"') && value.ends_with('"') {
-        Ok(value.trim_matches('"').to_string())
-    } else {
-        Err(())
+use std::io::{self, BufRead, Read};
+
+use crate::ParseLimits;
+
+pub fn description_trim(value: &str) -> Result<&str, ()> {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or(())
+}
+
+/// Начало содержимого файла в виде текста без BOM для определения формата
+pub fn sniff_text(data: &[u8]) -> String {
+    let text = String::from_utf8_lossy(data);
+    text.strip_prefix('\u{feff}').unwrap_or(&text).to_string()
+}
+
+/// Отбрасывание символов перевода строки в конце строки
+pub fn trim_line_end(line: &[u8]) -> &[u8] {
+    line.strip_suffix(b"\n")
+        .map(|l| l.strip_suffix(b"\r").unwrap_or(l))
+        .unwrap_or(line)
+}
+
+/// Ошибка чтения строки с учётом ограничений
+pub enum LineError {
+    /// Ошибка ввода-вывода
+    Io(io::Error),
+    /// Превышена максимальная длина строки
+    LineTooLong(usize),
+    /// Превышен максимальный размер входных данных
+    InputTooLarge(u64),
+}
+
+/// Построчное чтение с учётом [`ParseLimits`]
+pub struct LineReader {
+    limits: ParseLimits,
+    consumed: u64,
+    /// Длина текущей логической строки
+    length: usize,
+}
+
+impl LineReader {
+    pub fn new(limits: ParseLimits) -> Self {
+        Self {
+            limits,
+            consumed: 0,
+            length: 0,
+        }
+    }
+
+    /// Начало новой логической строки
+    pub fn reset(&mut self) {
+        self.length = 0;
+    }
+
+    /// Чтение строки вместе с символами перевода строки.
+    /// Возвращает `false`, если поток закончился.
+    /// Без вызова [`LineReader::reset`] длина строк суммируется
+    pub fn read<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        buf: &mut Vec<u8>,
+    ) -> Result<bool, LineError> {
+        buf.clear();
+
+        let mut limit = u64::MAX;
+        if let Some(max) = self.limits.max_line_length {
+            // два байта на \r\n, чтобы отличить длинную строку от строки ровно в max байт
+            limit = (max.saturating_sub(self.length) as u64).saturating_add(2);
+        }
+        if let Some(max) = self.limits.max_input_bytes {
+            limit = limit.min(max.saturating_sub(self.consumed).saturating_add(1));
+        }
+
+        let n = reader
+            .take(limit)
+            .read_until(b'\n', buf)
+            .map_err(LineError::Io)?;
+        self.consumed += n as u64;
+        self.length += trim_line_end(buf).len();
+
+        if let Some(max) = self.limits.max_input_bytes
+            && self.consumed > max
+        {
+            return Err(LineError::InputTooLarge(max));
+        }
+        if let Some(max) = self.limits.max_line_length
+            && self.length > max
+        {
+            if !buf.ends_with(b"\n") {
+                let skipped = reader.skip_until(b'\n').map_err(LineError::Io)?;
+                self.consumed += skipped as u64;
+            }
+            return Err(LineError::LineTooLong(max));
+        }
+
+        Ok(n > 0)
     }
 }
@@ -0,0 +1,572 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{BufRead, BufReader, Read, Write},
+    sync::Arc,
+};
+
+use crate::{
+    Diagnostic, Format, ParseMode, ParseOptions, ParserType, Transaction,
+    compression::Compression,
+    detect_single,
+    errors::{ParserError, WriteError},
+};
+
+/// Проверка начала содержимого файла
+pub type Sniffer = Arc<dyn Fn(&[u8]) -> bool + Send + Sync>;
+
+/// Формат, зарегистрированный в [`FormatRegistry`]
+#[derive(Clone)]
+pub struct FormatEntry {
+    name: String,
+    extensions: Vec<String>,
+    sniffers: Vec<Sniffer>,
+    format: Arc<dyn Format>,
+}
+
+impl FormatEntry {
+    /// Создание описания формата
+    pub fn new(name: impl Into<String>, format: impl Format + 'static) -> Self {
+        Self {
+            name: name.into(),
+            extensions: Vec::new(),
+            sniffers: Vec::new(),
+            format: Arc::new(format),
+        }
+    }
+
+    /// Добавление расширения файла без точки, например `csv`
+    pub fn extension(mut self, extension: impl Into<String>) -> Self {
+        self.extensions.push(extension.into());
+        self
+    }
+
+    /// Добавление проверки содержимого файла
+    pub fn sniffer(mut self, sniffer: impl Fn(&[u8]) -> bool + Send + Sync + 'static) -> Self {
+        self.sniffers.push(Arc::new(sniffer));
+        self
+    }
+
+    /// Название формата
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Расширения файлов формата
+    pub fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    /// Реализация формата
+    pub fn format(&self) -> &dyn Format {
+        self.format.as_ref()
+    }
+
+    /// Подходит ли начало содержимого файла под формат
+    pub fn matches(&self, data: &[u8]) -> bool {
+        self.sniffers.iter().any(|sniffer| sniffer(data))
+    }
+}
+
+impl fmt::Debug for FormatEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FormatEntry")
+            .field("name", &self.name)
+            .field("extensions", &self.extensions)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Формат файла вместе со сжатием
+#[derive(Debug, Clone, Copy)]
+pub struct FileFormat<'a> {
+    /// Формат записей
+    pub entry: &'a FormatEntry,
+    /// Сжатие файла
+    pub compression: Compression,
+}
+
+/// Распакованный поток вместе с форматом его записей,
+/// создаётся в [`FormatRegistry::open`]
+pub struct RecordSource<'a> {
+    reader: Box<dyn BufRead + 'a>,
+    format: Arc<dyn Format>,
+}
+
+impl RecordSource<'_> {
+    /// Потоковое чтение транзакций по одной записи
+    pub fn records(&mut self) -> Box<dyn Iterator<Item = Result<Transaction, ParserError>> + '_> {
+        self.format.read_records(&mut self.reader)
+    }
+}
+
+/// Реестр форматов, выбираемых по расширению файла или по содержимому.
+/// [`FormatRegistry::default`] содержит встроенные форматы [`ParserType`]
+#[derive(Debug, Clone)]
+pub struct FormatRegistry {
+    formats: Vec<FormatEntry>,
+    extensions: HashMap<String, usize>,
+}
+
+impl Default for FormatRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        for parser_type in ParserType::all() {
+            registry.register(parser_type.format());
+        }
+        registry
+    }
+}
+
+impl FormatRegistry {
+    /// Создание пустого реестра
+    pub fn new() -> Self {
+        Self {
+            formats: Vec::new(),
+            extensions: HashMap::new(),
+        }
+    }
+
+    /// Регистрация формата.
+    /// Формат с тем же названием заменяется целиком вместе с расширениями и проверками
+    /// содержимого. Расширения, уже занятые другим форматом, переходят к новому формату
+    pub fn register(&mut self, format: FormatEntry) -> &mut Self {
+        let position = match self.formats.iter().position(|f| f.name == format.name) {
+            Some(position) => {
+                self.extensions.retain(|_, &mut p| p != position);
+                self.formats[position] = format;
+                position
+            }
+            None => {
+                self.formats.push(format);
+                self.formats.len() - 1
+            }
+        };
+        for extension in &self.formats[position].extensions {
+            self.extensions.insert(extension.clone(), position);
+        }
+        self
+    }
+
+    /// Зарегистрированные форматы
+    pub fn formats(&self) -> &[FormatEntry] {
+        &self.formats
+    }
+
+    /// Формат по расширению файла без точки
+    pub fn by_extension(&self, extension: &str) -> Option<&FormatEntry> {
+        self.extensions
+            .get(extension)
+            .map(|&position| &self.formats[position])
+    }
+
+    /// Формат по пути к файлу. Расширение сжатия, например `.gz`, пропускается
+    pub fn by_path(&self, path: &str) -> Result<&FormatEntry, ParserError> {
+        let (_, path) = Compression::from_path(path);
+        path.rsplit_once('.')
+            .and_then(|(_, extension)| self.by_extension(extension))
+            .ok_or(ParserError::UnknownExt)
+    }
+
+    /// Формат и сжатие по пути к файлу
+    pub fn file_format(&self, path: &str) -> Result<FileFormat<'_>, ParserError> {
+        Ok(FileFormat {
+            entry: self.by_path(path)?,
+            compression: Compression::from_path(path).0,
+        })
+    }
+
+    /// Формат и сжатие по названию формата или расширению с необязательным
+    /// расширением сжатия, например `csv` или `bin.gz`
+    pub fn parse_format(&self, name: &str) -> Result<FileFormat<'_>, ParserError> {
+        let (compression, name) = Compression::from_path(name);
+        let entry = self
+            .formats
+            .iter()
+            .find(|format| format.name == name)
+            .or_else(|| self.by_extension(name))
+            .ok_or_else(|| ParserError::UnknownFormat(name.to_string()))?;
+        Ok(FileFormat { entry, compression })
+    }
+
+    /// Определение формата по началу содержимого файла.
+    /// Если содержимое подходит под несколько форматов, возвращается [`ParserError::AmbiguousFormat`]
+    pub fn detect(&self, data: &[u8]) -> Result<&FormatEntry, ParserError> {
+        detect_single(&self.formats, |format| format.matches(data))
+    }
+
+    /// Чтение транзаций из файла в режиме [`ParseMode::Strict`].
+    /// Файлы со сжатием, например `.csv.gz`, распаковываются при чтении
+    pub fn from_read<R: Read>(
+        &self,
+        reader: &mut R,
+        from: &str,
+    ) -> Result<Vec<Transaction>, ParserError> {
+        let entry = self.by_path(from)?;
+        let (compression, _) = Compression::from_path(from);
+        let mut reader = BufReader::new(compression.reader(reader)?);
+        read_strict(entry.format(), &mut reader)
+    }
+
+    /// Запись транзаций в файл.
+    /// Для файлов со сжатием, например `.csv.gz`, данные сжимаются при записи
+    pub fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        transactions: &[Transaction],
+        to: &str,
+    ) -> Result<(), ParserError> {
+        let entry = self.by_path(to)?;
+        let (compression, _) = Compression::from_path(to);
+        let mut writer = compression.writer(writer)?;
+
+        entry
+            .format()
+            .write_transactions(&mut writer, transactions)?;
+        writer.finish().map_err(WriteError::Write)?;
+
+        Ok(())
+    }
+
+    /// Чтение транзаций в режиме [`ParseMode::Strict`]
+    /// с определением сжатия и формата по содержимому
+    pub fn from_read_auto<R: Read>(&self, reader: &mut R) -> Result<Vec<Transaction>, ParserError> {
+        let mut reader = BufReader::new(reader);
+        let compression = Compression::detect(reader.fill_buf().map_err(ParserError::Read)?);
+        let mut reader = BufReader::new(compression.reader(reader)?);
+        let entry = self.detect(reader.fill_buf().map_err(ParserError::Read)?)?;
+        read_strict(entry.format(), &mut reader)
+    }
+
+    /// Чтение транзаций из файла с заданными настройками разбора.
+    /// В режиме [`ParseMode::Lenient`] некорректные записи пропускаются,
+    /// а ошибки возвращаются вместе с прочитанными транзакциями
+    pub fn from_read_with_options<R: Read>(
+        &self,
+        reader: &mut R,
+        from: &str,
+        options: ParseOptions,
+    ) -> Result<(Vec<Transaction>, Vec<Diagnostic>), ParserError> {
+        let entry = self.by_path(from)?;
+        let (compression, _) = Compression::from_path(from);
+        let mut reader = BufReader::new(compression.reader(reader)?);
+        entry
+            .format()
+            .with_parse_options(options)
+            .read_transactions(&mut reader)
+    }
+
+    /// Потоковая конвертация транзакций из одного формата в другой.
+    /// Если расширение входного файла неизвестно, сжатие и формат определяются по содержимому.
+    /// Возвращает количество записанных транзакций
+    pub fn convert<R: Read, W: Write>(
+        &self,
+        reader: &mut R,
+        from: &str,
+        writer: &mut W,
+        to: &str,
+    ) -> Result<usize, ParserError> {
+        let to = self.file_format(to)?;
+        let from = self.file_format(from).ok();
+        self.convert_with(reader, from, writer, to)
+    }
+
+    /// Потоковая конвертация с заданными форматами.
+    /// Если входной формат не задан, сжатие и формат определяются по содержимому.
+    /// Возвращает количество записанных транзакций
+    pub fn convert_with<R: Read, W: Write>(
+        &self,
+        reader: &mut R,
+        from: Option<FileFormat<'_>>,
+        writer: &mut W,
+        to: FileFormat<'_>,
+    ) -> Result<usize, ParserError> {
+        let mut source = self.open(reader, from)?;
+        self.write_records(writer, to, source.records())
+    }
+
+    /// Открытие источника транзакций для потокового чтения.
+    /// Если формат не задан, сжатие и формат определяются по содержимому
+    pub fn open<'a, R: Read + 'a>(
+        &self,
+        reader: R,
+        from: Option<FileFormat<'_>>,
+    ) -> Result<RecordSource<'a>, ParserError> {
+        let mut reader = BufReader::new(reader);
+        let compression = match from {
+            Some(from) => from.compression,
+            None => Compression::detect(reader.fill_buf().map_err(ParserError::Read)?),
+        };
+        let mut reader = BufReader::new(compression.reader(reader)?);
+        let entry = match from {
+            Some(from) => from.entry,
+            None => self.detect(reader.fill_buf().map_err(ParserError::Read)?)?,
+        };
+        Ok(RecordSource {
+            reader: Box::new(reader),
+            format: Arc::clone(&entry.format),
+        })
+    }
+
+    /// Потоковая запись транзакций.
+    /// Чтение останавливается на первой ошибке, которая возвращается без изменений.
+    /// Возвращает количество записанных транзакций
+    pub fn write_records<W: Write, E: From<ParserError>>(
+        &self,
+        writer: &mut W,
+        to: FileFormat<'_>,
+        records: impl IntoIterator<Item = Result<Transaction, E>>,
+    ) -> Result<usize, E> {
+        let mut encoder = to.compression.writer(writer)?;
+        let mut writer = to.entry.format().create_writer(&mut encoder);
+
+        let mut count = 0;
+        for transaction in records {
+            writer.write(&transaction?).map_err(ParserError::from)?;
+            count += 1;
+        }
+        writer.finish().map_err(ParserError::from)?;
+        drop(writer);
+        encoder
+            .finish()
+            .map_err(|e| ParserError::from(WriteError::Write(e)))?;
+
+        Ok(count)
+    }
+}
+
+/// Чтение транзакций в режиме [`ParseMode::Strict`] с остальными настройками формата,
+/// чтобы некорректные записи не пропускались без сообщения об ошибке
+fn read_strict(
+    format: &dyn Format,
+    reader: &mut dyn BufRead,
+) -> Result<Vec<Transaction>, ParserError> {
+    let options = ParseOptions {
+        mode: ParseMode::Strict,
+        ..format.parse_options()
+    };
+    let (transactions, _) = format
+        .with_parse_options(options)
+        .read_transactions(reader)?;
+    Ok(transactions)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use thiserror::Error;
+
+    use super::*;
+    use crate::TransactionWriter;
+
+    /// Формат из одних идентификаторов транзакций, по одному на строку после заголовка
+    #[derive(Default)]
+    struct IdsFormat {
+        options: ParseOptions,
+    }
+
+    #[derive(Debug, Error)]
+    #[error("Некорректный идентификатор {0}")]
+    struct IdsError(String);
+
+    struct IdsWriter<'a> {
+        writer: &'a mut dyn Write,
+        header_written: bool,
+    }
+
+    impl TransactionWriter for IdsWriter<'_> {
+        fn write(&mut self, t: &Transaction) -> Result<(), WriteError> {
+            if !self.header_written {
+                writeln!(self.writer, "ids")?;
+                self.header_written = true;
+            }
+            writeln!(self.writer, "{}", t.tx_id).map_err(WriteError::Write)
+        }
+
+        fn finish(&mut self) -> Result<(), WriteError> {
+            self.writer.flush().map_err(WriteError::Write)
+        }
+    }
+
+    impl Format for IdsFormat {
+        fn parse_options(&self) -> ParseOptions {
+            self.options
+        }
+
+        fn with_parse_options(&self, options: ParseOptions) -> Box<dyn Format> {
+            Box::new(Self { options })
+        }
+
+        fn read_records<'a>(
+            &self,
+            reader: &'a mut dyn BufRead,
+        ) -> Box<dyn Iterator<Item = Result<Transaction, ParserError>> + 'a> {
+            Box::new(reader.lines().skip(1).map(|line| {
+                let line = line.map_err(ParserError::Read)?;
+                let tx_id = line
+                    .parse()
+                    .map_err(|_| ParserError::Custom(Box::new(IdsError(line))))?;
+                Ok(Transaction {
+                    tx_id,
+                    ..Default::default()
+                })
+            }))
+        }
+
+        fn create_writer<'a>(&self, writer: &'a mut dyn Write) -> Box<dyn TransactionWriter + 'a> {
+            Box::new(IdsWriter {
+                writer,
+                header_written: false,
+            })
+        }
+    }
+
+    fn get_registry() -> FormatRegistry {
+        let mut registry = FormatRegistry::default();
+        registry.register(
+            FormatEntry::new("ids", IdsFormat::default())
+                .extension("ids")
+                .extension("txt")
+                .sniffer(|data| data.starts_with(b"ids\n")),
+        );
+        registry
+    }
+
+    #[test]
+    fn test_success_register() {
+        let registry = get_registry();
+        assert_eq!(registry.by_path("a.ids").unwrap().name(), "ids");
+        assert_eq!(registry.by_path("a.txt.gz").unwrap().name(), "ids");
+        assert_eq!(registry.by_path("a.csv").unwrap().name(), "csv");
+        assert!(matches!(
+            registry.by_path("a.xml"),
+            Err(ParserError::UnknownExt)
+        ));
+        assert_eq!(registry.detect(b"ids\n1\n").unwrap().name(), "ids");
+
+        let transactions: Vec<Transaction> = (1..=3)
+            .map(|tx_id| Transaction {
+                tx_id,
+                ..Default::default()
+            })
+            .collect();
+        let mut data = Vec::new();
+        registry
+            .convert(
+                &mut Cursor::new("ids\n1\n2\n3\n"),
+                "a.ids",
+                &mut data,
+                "b.csv",
+            )
+            .unwrap();
+        assert_eq!(
+            registry
+                .from_read(&mut Cursor::new(&data), "b.csv")
+                .unwrap(),
+            transactions
+        );
+
+        let mut data = Vec::new();
+        registry
+            .write_to(&mut data, &transactions, "a.ids")
+            .unwrap();
+        assert_eq!(data, b"ids\n1\n2\n3\n");
+        assert_eq!(
+            registry.from_read_auto(&mut Cursor::new(&data)).unwrap(),
+            transactions
+        );
+    }
+
+    #[test]
+    fn test_success_override() {
+        let mut registry = FormatRegistry::default();
+        let count = registry.formats().len();
+        registry.register(
+            FormatEntry::new("csv", IdsFormat::default())
+                .extension("ids")
+                .sniffer(|data| data.starts_with(b"ids\n")),
+        );
+        assert_eq!(registry.formats().len(), count);
+
+        let entry = registry.parse_format("csv").unwrap().entry;
+        assert_eq!(entry.extensions(), ["ids"]);
+        assert_eq!(registry.by_path("a.ids").unwrap().name(), "csv");
+        assert!(matches!(
+            registry.by_path("a.csv"),
+            Err(ParserError::UnknownExt)
+        ));
+        assert_eq!(registry.detect(b"ids\n1\n").unwrap().name(), "csv");
+        assert!(matches!(
+            registry.detect(b"TX_ID,TX_TYPE,FROM_USER_ID\n"),
+            Err(ParserError::UnknownContent)
+        ));
+
+        let transactions = registry
+            .from_read(&mut Cursor::new("ids\n1\n2\n"), "a.ids")
+            .unwrap();
+        assert_eq!(transactions.len(), 2);
+    }
+
+    #[test]
+    fn test_success_convert_with() {
+        let registry = get_registry();
+        let from = registry.parse_format("ids").unwrap();
+        let to = registry.parse_format("jsonl").unwrap();
+        assert_eq!(to.entry.name(), "jsonl");
+        assert_eq!(to.compression, Compression::None);
+
+        let mut data = Vec::new();
+        let count = registry
+            .convert_with(&mut Cursor::new("ids\n1\n2\n"), Some(from), &mut data, to)
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let mut ids = Vec::new();
+        registry
+            .convert_with(&mut Cursor::new(&data), None, &mut ids, from)
+            .unwrap();
+        assert_eq!(ids, b"ids\n1\n2\n");
+
+        assert!(matches!(
+            registry.parse_format("xml.gz"),
+            Err(ParserError::UnknownFormat(name)) if name == "xml"
+        ));
+    }
+
+    #[test]
+    fn test_error_custom() {
+        let result = get_registry()
+            .from_read(&mut Cursor::new("ids\n1\nx\n"), "a.ids")
+            .unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Ошибка парсера: Некорректный идентификатор x"
+        );
+
+        // from_read не пропускает ошибки молча, даже если формат зарегистрирован в режиме Lenient
+        let mut registry = FormatRegistry::new();
+        let lenient = IdsFormat {
+            options: ParseOptions {
+                mode: ParseMode::Lenient,
+                ..Default::default()
+            },
+        };
+        registry.register(FormatEntry::new("ids", lenient).extension("ids"));
+        let mut data = Cursor::new("ids\n1\nx\n");
+        assert!(registry.from_read(&mut data, "a.ids").is_err());
+
+        let result = FormatRegistry::new()
+            .from_read_auto(&mut Cursor::new("ids\n"))
+            .unwrap_err();
+        assert!(matches!(result, ParserError::UnknownContent));
+
+        let mut registry = get_registry();
+        registry.register(
+            FormatEntry::new("ids2", IdsFormat::default()).sniffer(|data| data.starts_with(b"ids")),
+        );
+        let result = registry.detect(b"ids\n1\n").unwrap_err();
+        assert!(matches!(result, ParserError::AmbiguousFormat));
+        assert_eq!(registry.detect(b"ids2\n").unwrap().name(), "ids2");
+    }
+}
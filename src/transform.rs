@@ -0,0 +1,287 @@
+use std::{cmp::Ordering, collections::HashSet, str::FromStr};
+
+use strum::{Display, EnumString};
+
+use crate::{Field, Transaction, errors::TransformError};
+
+/// Направление сортировки
+#[derive(Debug, Default, Clone, Copy, PartialEq, Display, EnumString)]
+#[strum(ascii_case_insensitive)]
+pub enum Direction {
+    /// По возрастанию
+    #[default]
+    #[strum(serialize = "asc")]
+    Asc,
+    /// По убыванию
+    #[strum(serialize = "desc")]
+    Desc,
+}
+
+/// Ключ сортировки в виде `FIELD`, `FIELD:asc` или `FIELD:desc`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortKey {
+    /// Поле
+    pub field: Field,
+    /// Направление
+    pub direction: Direction,
+}
+
+impl FromStr for SortKey {
+    type Err = TransformError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || TransformError::InvalidSortKey(s.to_string());
+        let (field, direction) = match s.split_once(':') {
+            Some((field, direction)) => (field, direction.parse().map_err(|_| invalid())?),
+            None => (s, Direction::Asc),
+        };
+        Ok(Self {
+            field: field.trim().parse().map_err(|_| invalid())?,
+            direction,
+        })
+    }
+}
+
+/// Какую из повторяющихся транзакций оставить
+#[derive(Debug, Default, Clone, Copy, PartialEq, Display, EnumString)]
+#[strum(ascii_case_insensitive)]
+pub enum Keep {
+    /// Первую
+    #[default]
+    #[strum(serialize = "first")]
+    First,
+    /// Последнюю
+    #[strum(serialize = "last")]
+    Last,
+}
+
+/// Преобразования набора транзакций.
+/// Применяются в порядке: удаление повторов, сортировка, пропуск `offset` записей, ограничение `limit`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Transforms {
+    /// Ключи сортировки в порядке приоритета
+    pub sort_by: Vec<SortKey>,
+    /// Поля, по которым транзакции считаются повторяющимися
+    pub dedupe_by: Vec<Field>,
+    /// Какую из повторяющихся транзакций оставить
+    pub keep: Keep,
+    /// Количество пропускаемых транзакций
+    pub offset: usize,
+    /// Максимальное количество транзакций
+    pub limit: Option<usize>,
+}
+
+impl Transforms {
+    /// Можно ли применить преобразования без чтения всех транзакций
+    pub fn is_streaming(&self) -> bool {
+        self.sort_by.is_empty() && (self.dedupe_by.is_empty() || self.keep == Keep::First)
+    }
+
+    /// Применение преобразований к набору транзакций
+    pub fn apply(&self, transactions: Vec<Transaction>) -> Vec<Transaction> {
+        let mut transactions = dedupe_by(transactions, &self.dedupe_by, self.keep);
+        sort_by(&mut transactions, &self.sort_by);
+        transactions
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+
+    /// Применение преобразований к потоку транзакций.
+    /// Если преобразования требуют всех транзакций ([`Transforms::is_streaming`]),
+    /// поток читается целиком до первой ошибки. Ошибки передаются дальше без изменений
+    pub fn apply_iter<'a, E: 'a>(
+        &'a self,
+        records: impl Iterator<Item = Result<Transaction, E>> + 'a,
+    ) -> Box<dyn Iterator<Item = Result<Transaction, E>> + 'a> {
+        if !self.is_streaming() {
+            return match records.collect::<Result<Vec<_>, E>>() {
+                Ok(transactions) => Box::new(self.apply(transactions).into_iter().map(Ok)),
+                Err(e) => Box::new(std::iter::once(Err(e))),
+            };
+        }
+
+        let mut seen = HashSet::new();
+        let mut skipped = 0;
+        let mut taken = 0;
+        Box::new(
+            records
+                .filter(move |record| match record {
+                    Ok(t) if !self.dedupe_by.is_empty() => seen.insert(key(t, &self.dedupe_by)),
+                    _ => true,
+                })
+                .filter(move |record| {
+                    if record.is_ok() && skipped < self.offset {
+                        skipped += 1;
+                        return false;
+                    }
+                    true
+                })
+                .take_while(move |record| {
+                    if record.is_ok() {
+                        taken += 1;
+                    }
+                    self.limit.is_none_or(|limit| taken <= limit)
+                }),
+        )
+    }
+}
+
+/// Устойчивая сортировка транзакций по ключам в порядке приоритета
+pub fn sort_by(transactions: &mut [Transaction], keys: &[SortKey]) {
+    if keys.is_empty() {
+        return;
+    }
+    transactions.sort_by(|a, b| {
+        keys.iter()
+            .map(|key| match key.direction {
+                Direction::Asc => compare(a, b, &key.field),
+                Direction::Desc => compare(b, a, &key.field),
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+/// Удаление транзакций с повторяющимися значениями полей.
+/// Оставшиеся транзакции сохраняют исходный порядок
+pub fn dedupe_by(transactions: Vec<Transaction>, fields: &[Field], keep: Keep) -> Vec<Transaction> {
+    if fields.is_empty() {
+        return transactions;
+    }
+
+    let mut seen = HashSet::new();
+    match keep {
+        Keep::First => transactions
+            .into_iter()
+            .filter(|t| seen.insert(key(t, fields)))
+            .collect(),
+        Keep::Last => {
+            let mut transactions: Vec<Transaction> = transactions
+                .into_iter()
+                .rev()
+                .filter(|t| seen.insert(key(t, fields)))
+                .collect();
+            transactions.reverse();
+            transactions
+        }
+    }
+}
+
+fn key(t: &Transaction, fields: &[Field]) -> Vec<String> {
+    fields.iter().map(|field| t.get_value(field)).collect()
+}
+
+fn compare(a: &Transaction, b: &Transaction, field: &Field) -> Ordering {
+    match field {
+        Field::TxId => a.tx_id.cmp(&b.tx_id),
+        Field::FromUserId => a.from_user_id.cmp(&b.from_user_id),
+        Field::ToUserId => a.to_user_id.cmp(&b.to_user_id),
+        Field::Amount => a.amount.cmp(&b.amount),
+        Field::Timestamp => a.timestamp.cmp(&b.timestamp),
+        Field::Description => a.description.cmp(&b.description),
+        Field::TxType | Field::Status => a.get_value(field).cmp(&b.get_value(field)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_transaction(tx_id: u64, amount: u64, timestamp: i64) -> Transaction {
+        Transaction {
+            tx_id,
+            amount,
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    fn get_transactions() -> Vec<Transaction> {
+        vec![
+            get_transaction(3, 100, 20),
+            get_transaction(1, 200, 10),
+            get_transaction(2, 300, 20),
+            get_transaction(1, 400, 30),
+        ]
+    }
+
+    fn ids(transactions: &[Transaction]) -> Vec<(u64, u64)> {
+        transactions.iter().map(|t| (t.tx_id, t.amount)).collect()
+    }
+
+    #[test]
+    fn test_success_sort_by() {
+        let mut transactions = get_transactions();
+        let keys = ["TIMESTAMP:desc".parse().unwrap(), "tx_id".parse().unwrap()];
+        sort_by(&mut transactions, &keys);
+        assert_eq!(ids(&transactions), [(1, 400), (2, 300), (3, 100), (1, 200)]);
+
+        sort_by(&mut transactions, &["TX_ID:ASC".parse().unwrap()]);
+        assert_eq!(ids(&transactions), [(1, 400), (1, 200), (2, 300), (3, 100)]);
+    }
+
+    #[test]
+    fn test_success_dedupe_by() {
+        let result = dedupe_by(get_transactions(), &[Field::TxId], Keep::First);
+        assert_eq!(ids(&result), [(3, 100), (1, 200), (2, 300)]);
+
+        let result = dedupe_by(get_transactions(), &[Field::TxId], Keep::Last);
+        assert_eq!(ids(&result), [(3, 100), (2, 300), (1, 400)]);
+
+        let result = dedupe_by(get_transactions(), &[Field::Timestamp], Keep::First);
+        assert_eq!(ids(&result), [(3, 100), (1, 200), (1, 400)]);
+    }
+
+    #[test]
+    fn test_success_apply() {
+        let transforms = Transforms {
+            dedupe_by: vec![Field::TxId],
+            offset: 1,
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert!(transforms.is_streaming());
+        assert_eq!(ids(&transforms.apply(get_transactions())), [(1, 200)]);
+
+        let records = get_transactions().into_iter().map(Ok::<_, ()>);
+        let result: Vec<Transaction> = transforms.apply_iter(records).flatten().collect();
+        assert_eq!(ids(&result), [(1, 200)]);
+
+        let transforms = Transforms {
+            sort_by: vec!["AMOUNT:desc".parse().unwrap()],
+            keep: Keep::Last,
+            ..transforms
+        };
+        assert!(!transforms.is_streaming());
+        let records = get_transactions().into_iter().map(Ok::<_, ()>);
+        let result: Vec<Transaction> = transforms.apply_iter(records).flatten().collect();
+        assert_eq!(ids(&result), [(2, 300)]);
+    }
+
+    #[test]
+    fn test_error_apply_iter() {
+        let transforms = Transforms {
+            offset: 2,
+            ..Default::default()
+        };
+        let records = vec![Ok(get_transaction(1, 0, 0)), Err("ошибка")];
+        let result: Vec<_> = transforms.apply_iter(records.into_iter()).collect();
+        assert_eq!(result, [Err("ошибка")]);
+
+        let transforms = Transforms {
+            sort_by: vec!["TX_ID".parse().unwrap()],
+            ..Default::default()
+        };
+        let records = vec![Ok(get_transaction(1, 0, 0)), Err("ошибка")];
+        let result: Vec<_> = transforms.apply_iter(records.into_iter()).collect();
+        assert_eq!(result, [Err("ошибка")]);
+
+        let result = "AMOUNT:up".parse::<SortKey>().unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "Некорректный ключ сортировки AMOUNT:up, ожидается FIELD, FIELD:asc или FIELD:desc"
+        );
+    }
+}